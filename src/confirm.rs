@@ -0,0 +1,133 @@
+#[cfg(feature = "async")]
+use crate::Status;
+use crate::{Action, KeyCode, KeyEvent, KeyModifiers, Menu, MenuLike};
+use crossterm::{style::Print, terminal};
+use std::collections::HashSet;
+use std::error::Error as StdError;
+
+/// A yes/no prompt with the answers side by side after the title. y and n answer right away,
+/// left and right move between the answers.
+pub struct Confirm {
+    menu: Menu,
+}
+
+impl Confirm {
+    /// `default` is the answer highlighted first.
+    pub fn new(title: String, default: bool) -> Confirm {
+        let mut confirm = Confirm {
+            menu: Menu::default(),
+        };
+        confirm.title(title);
+        confirm.options(vec![String::from("Yes"), String::from("No")]);
+        confirm.selected_index(if default { 0 } else { 1 });
+        for (code, action) in [
+            (KeyCode::Left, Action::Up),
+            (KeyCode::Right, Action::Down),
+            (KeyCode::Char('y'), Action::Choose(0)),
+            (KeyCode::Char('n'), Action::Choose(1)),
+        ] {
+            confirm.bind(KeyEvent::new(code, KeyModifiers::NONE), action);
+        }
+        confirm
+    }
+
+    /// Shows the prompt and returns whether it was answered with yes. Esc answers no.
+    pub fn confirm(&mut self) -> Result<bool, Box<dyn StdError>> {
+        let selected = self.run()?;
+        Ok(selected.is_some_and(|selected| selected.contains(&0)))
+    }
+
+    /// Like `confirm`, on top of `run_async`. Ctrl-c answers no instead of exiting.
+    #[cfg(feature = "async")]
+    pub async fn confirm_async(&mut self) -> Result<bool, Box<dyn StdError>> {
+        let outcome = self.run_async().await?;
+        let yes = outcome
+            .selected
+            .is_some_and(|selected| selected.contains(&0));
+        Ok(outcome.status == Status::Submitted && yes)
+    }
+}
+
+impl Default for Confirm {
+    fn default() -> Self {
+        Confirm::new(String::from("Are you sure?"), true)
+    }
+}
+
+impl MenuLike for Confirm {
+    fn get_menu_mut(&mut self) -> &mut Menu {
+        &mut self.menu
+    }
+
+    fn get_menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    /// The answers share the title's last line, and only need a line of their own without a
+    /// title.
+    fn option_lines_for(&self, _rows: u16, _fixed_lines: usize) -> usize {
+        self.format_title().is_empty() as usize
+    }
+
+    fn on_up_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        if self.menu.selected_index > 0 {
+            self.menu.selected_index -= 1;
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_down_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        if self.menu.selected_index + 1 < self.menu.options.len() {
+            self.menu.selected_index += 1;
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Esc gives up on the prompt, which `confirm` takes as no.
+    fn describe(&self, action: Action) -> String {
+        match action {
+            Action::Cancel => String::from("no"),
+            action => self.menu.describe(action),
+        }
+    }
+
+    /// Accepts an answer's number or label, or the start of its label, ignoring case.
+    fn parse_answer(&self, answer: &str) -> Result<HashSet<usize>, String> {
+        let answer = answer.trim().to_lowercase();
+        self.menu
+            .options
+            .iter()
+            .enumerate()
+            .position(|(index, option)| {
+                let option = option.to_lowercase();
+                answer == (index + 1).to_string() || option.starts_with(&answer)
+            })
+            .map(|index| HashSet::from([index]))
+            .ok_or_else(|| format!("Answer {}", self.menu.options.join(" or ")))
+    }
+
+    /// Draws the answers on the last line of the title.
+    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
+        let title = self.format_title();
+        let footer_lines = self.footer_lines();
+        let (columns, _) = terminal::size()?;
+        let mut title_lines: Vec<&str> = title.lines().collect();
+        let last_line = title_lines.pop();
+        self.menu.start_line_frame(&title_lines)?;
+        if let Some(line) = last_line {
+            queue!(self.menu.writer, Print(line), Print(" "))?;
+        }
+        for index in 0..self.menu.options.len() {
+            if index > 0 {
+                queue!(self.menu.writer, Print(" "))?;
+            }
+            self.draw_option(index)?;
+        }
+        self.menu
+            .finish_line_frame(title_lines.len(), &footer_lines, columns)?;
+        self.menu.writer.flush()?;
+        Ok(())
+    }
+}
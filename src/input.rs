@@ -0,0 +1,564 @@
+use crate::{Action, Color, Menu, MenuLike, Outcome, Status, TimeoutAnswer};
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    style::{Print, ResetColor, SetForegroundColor},
+    terminal,
+};
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::io::stdin;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Checks an answer before it is accepted, returning the message to show when it is not.
+pub type Validator = Box<dyn Fn(&str) -> Result<(), String>>;
+
+/// The text being edited and the cursor, counted in chars.
+#[derive(Default)]
+struct LineEditor {
+    text: Vec<char>,
+    cursor: usize,
+}
+
+impl LineEditor {
+    fn set(&mut self, text: &str) {
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+    }
+
+    fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Where the word before the cursor starts.
+    fn word_start(&self) -> usize {
+        let mut start = self.cursor;
+        while start > 0 && self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.text[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Where the word after the cursor ends.
+    fn word_end(&self) -> usize {
+        let mut end = self.cursor;
+        while end < self.text.len() && self.text[end].is_whitespace() {
+            end += 1;
+        }
+        while end < self.text.len() && !self.text[end].is_whitespace() {
+            end += 1;
+        }
+        end
+    }
+
+    /// The first char shown when `width` columns are shown with the cursor in view, keeping
+    /// as much as possible before the cursor.
+    fn window_start(&self, width: usize) -> usize {
+        let mut start = self.cursor;
+        let mut used = 0;
+        while start > 0 {
+            used += self.text[start - 1].width().unwrap_or(0);
+            // the cursor takes a column of its own after the text
+            if used + 1 > width {
+                break;
+            }
+            start -= 1;
+        }
+        start
+    }
+
+    /// Applies an editing key, returning whether the key was one.
+    fn edit(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.text.len(),
+            KeyCode::Char('b') if control => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('f') if control => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Char('w') if control => {
+                let start = self.word_start();
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            KeyCode::Char('u') if control => {
+                self.text.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if control => {
+                self.text.truncate(self.cursor);
+            }
+            KeyCode::Char(c) if !control && !alt => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if alt => {
+                let start = self.word_start();
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left if control || alt => self.cursor = self.word_start(),
+            KeyCode::Right if control || alt => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Backspace | KeyCode::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A one line text prompt. The answer is edited after the title, with readline style keys:
+/// ctrl-w deletes a word, ctrl-u and ctrl-k delete to the start and the end, and up and down
+/// go through the history.
+pub struct Input {
+    menu: Menu,
+    editor: LineEditor,
+    placeholder: String,
+    placeholder_foreground_color: Color,
+    default_value: Option<String>,
+    validator: Option<Validator>,
+    history: Vec<String>,
+    /// The history entry being edited, `None` for the new answer.
+    history_index: Option<usize>,
+    /// The new answer, kept while the history is browsed.
+    draft: String,
+}
+
+impl Input {
+    pub fn new(title: String) -> Input {
+        let mut input = Input {
+            menu: Menu::default(),
+            editor: LineEditor::default(),
+            placeholder: String::new(),
+            placeholder_foreground_color: Color::DarkGrey,
+            default_value: None,
+            validator: None,
+            history: Vec::new(),
+            history_index: None,
+            draft: String::new(),
+        };
+        input.title(title);
+        input.options(Vec::new());
+        input
+    }
+
+    /// Shows the prompt and returns the answer, or `None` when it was cancelled with esc.
+    /// Exits the process on ctrl-c like `run`.
+    pub fn read(&mut self) -> Result<Option<String>, Box<dyn StdError>> {
+        let outcome = self.run_outcome()?;
+        self.menu.exit_if_interrupted(&outcome);
+        Ok(self.answer(&outcome))
+    }
+
+    /// Like `read`, on top of `run_async`. Ctrl-c returns `None` instead of exiting.
+    #[cfg(feature = "async")]
+    pub async fn read_async(&mut self) -> Result<Option<String>, Box<dyn StdError>> {
+        let outcome = self.run_async().await?;
+        Ok(self.answer(&outcome))
+    }
+
+    /// The answer of a submitted `outcome`, which is added to the history. Clears the answer
+    /// for the next time the prompt is shown.
+    fn answer(&mut self, outcome: &Outcome) -> Option<String> {
+        let value = self.get_value();
+        self.editor.set("");
+        self.history_index = None;
+        if outcome.status != Status::Submitted {
+            return None;
+        }
+        if self.history.last() != Some(&value) && !value.is_empty() {
+            self.history.push(value.clone());
+        }
+        Some(value)
+    }
+
+    /// Dimmed text shown while the answer is empty.
+    pub fn placeholder(&mut self, placeholder: String) {
+        self.placeholder = placeholder;
+    }
+
+    pub fn placeholder_foreground_color(&mut self, color: Color) {
+        self.placeholder_foreground_color = color;
+    }
+
+    /// The answer when nothing is typed, shown in brackets after the title.
+    pub fn default_value(&mut self, default_value: String) {
+        self.default_value = Some(default_value);
+    }
+
+    /// Text the answer starts out with.
+    pub fn value(&mut self, value: String) {
+        self.editor.set(&value);
+    }
+
+    pub fn validator(&mut self, validator: Validator) {
+        self.validator = Some(validator);
+    }
+
+    /// Earlier answers, oldest first. Submitted answers are added to it.
+    pub fn history(&mut self, history: Vec<String>) {
+        self.history = history;
+    }
+
+    /// The answer typed so far, or the default value when nothing is typed.
+    pub fn get_value(&self) -> String {
+        match (&self.default_value, self.editor.text.is_empty()) {
+            (Some(default_value), true) => default_value.clone(),
+            _ => self.editor.text(),
+        }
+    }
+
+    pub fn get_history(&self) -> &Vec<String> {
+        &self.history
+    }
+
+    fn validate(&self, value: &str) -> Result<(), String> {
+        match &self.validator {
+            Some(validator) => validator(value),
+            None => Ok(()),
+        }
+    }
+
+    /// Steps through the history, towards older answers for `Up`.
+    fn browse_history(&mut self, older: bool) -> bool {
+        let index = match (self.history_index, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return false,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        if index.is_none() && self.history_index.is_none() {
+            return false;
+        }
+        if self.history_index.is_none() {
+            self.draft = self.editor.text();
+        }
+        self.history_index = index;
+        match index {
+            Some(index) => self.editor.set(&self.history[index]),
+            None => self.editor.set(&self.draft),
+        }
+        true
+    }
+
+    /// The title's last line and the default value, which the answer follows.
+    fn prompt(&self) -> (Vec<String>, String) {
+        let title = self.format_title();
+        let mut lines: Vec<String> = title.lines().map(String::from).collect();
+        let mut prompt = lines.pop().unwrap_or_default();
+        if let Some(default_value) = &self.default_value {
+            prompt = format!("{} [{}]", prompt, default_value);
+        }
+        if !prompt.is_empty() {
+            prompt.push(' ');
+        }
+        (lines, prompt.trim_start().to_string())
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Input::new(String::new())
+    }
+}
+
+impl MenuLike for Input {
+    fn get_menu_mut(&mut self) -> &mut Menu {
+        &mut self.menu
+    }
+
+    fn get_menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    fn format_answer(&self, _selected: Option<&HashSet<usize>>) -> String {
+        self.get_value()
+    }
+
+    fn run_fallback(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        let (title_lines, prompt) = self.prompt();
+        for line in title_lines {
+            writeln!(self.menu.writer, "{}", line)?;
+        }
+        loop {
+            write!(self.menu.writer, "{}", prompt)?;
+            self.menu.writer.flush()?;
+            let mut answer = String::new();
+            if stdin().read_line(&mut answer)? == 0 {
+                writeln!(self.menu.writer)?;
+                return Ok(Outcome::new(Status::Cancelled, None, None));
+            }
+            self.editor.set(answer.trim_end_matches(['\r', '\n']));
+            match self.validate(&self.get_value()) {
+                Ok(()) => return Ok(Outcome::new(Status::Submitted, None, None)),
+                Err(message) => writeln!(self.menu.writer, "{}", message)?,
+            }
+        }
+    }
+
+    /// Submits the answer as typed, like enter, unless the validator rejects it.
+    fn on_timeout(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        let submitted = !matches!(self.menu.timeout, Some((_, TimeoutAnswer::Cancel)))
+            && self.validate(&self.get_value()).is_ok();
+        let status = match submitted {
+            true => Status::Submitted,
+            false => Status::Cancelled,
+        };
+        Ok(Outcome::new(status, None, None))
+    }
+
+    fn handle_event(&mut self, event: Event) -> Result<Option<Outcome>, Box<dyn StdError>> {
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Resize(_, _) => {
+                self.redraw()?;
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+        self.menu.restart_timeout();
+        let action = self
+            .menu
+            .key_bindings
+            .iter()
+            .find(|binding| binding.key == key)
+            .map(|binding| binding.action);
+        let status = match action {
+            Some(Action::Submit) => match self.validate(&self.get_value()) {
+                Ok(()) => Status::Submitted,
+                Err(message) => {
+                    self.menu.status = Some(message);
+                    self.redraw()?;
+                    return Ok(None);
+                }
+            },
+            Some(Action::Cancel) => Status::Cancelled,
+            Some(Action::Interrupt) => Status::Interrupted,
+            _ => {
+                let edited = match key.code {
+                    KeyCode::Up if key.modifiers.is_empty() => self.browse_history(true),
+                    KeyCode::Down if key.modifiers.is_empty() => self.browse_history(false),
+                    _ => self.editor.edit(key),
+                };
+                if edited {
+                    self.menu.status = None;
+                    self.redraw()?;
+                }
+                return Ok(None);
+            }
+        };
+        self.restore_console()?;
+        Ok(Some(Outcome::new(status, None, Some(key))))
+    }
+
+    /// The text shares the title's last line, and only needs a line of its own without a
+    /// title.
+    fn option_lines_for(&self, _rows: u16, _fixed_lines: usize) -> usize {
+        self.format_title().is_empty() as usize
+    }
+
+    /// Draws the answer after the title, scrolled sideways to keep the cursor in view.
+    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
+        let (title_lines, prompt) = self.prompt();
+        let footer_lines = self.footer_lines();
+        let (columns, _) = terminal::size()?;
+        self.menu.start_line_frame(&title_lines)?;
+        let prompt_width = prompt.width();
+        let width = (columns as usize).saturating_sub(prompt_width + 1).max(1);
+        let start = self.editor.window_start(width);
+        queue!(self.menu.writer, Print(&prompt))?;
+        if self.editor.text.is_empty() {
+            let placeholder = fit(self.placeholder.chars(), width);
+            queue!(
+                self.menu.writer,
+                SetForegroundColor(self.placeholder_foreground_color),
+                Print(placeholder),
+                ResetColor
+            )?;
+        } else {
+            let text = fit(self.editor.text[start..].iter().copied(), width);
+            queue!(self.menu.writer, Print(text))?;
+        }
+        self.menu
+            .finish_line_frame(title_lines.len(), &footer_lines, columns)?;
+        let before: String = self.editor.text[start..self.editor.cursor].iter().collect();
+        let column = prompt_width + before.width();
+        queue!(
+            self.menu.writer,
+            cursor::MoveToColumn(column as u16 + 1),
+            cursor::Show
+        )?;
+        self.menu.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// As much of the start of `chars` as fits in `width` columns.
+fn fit(chars: impl Iterator<Item = char>, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut used = 0;
+    for c in chars {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            break;
+        }
+        fitted.push(c);
+    }
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str, cursor: usize) -> LineEditor {
+        LineEditor {
+            text: text.chars().collect(),
+            cursor,
+        }
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn window_start_keeps_the_cursor_in_view() {
+        assert_eq!(editor("abcdef", 6).window_start(10), 0);
+        assert_eq!(editor("abcdef", 6).window_start(4), 3);
+        assert_eq!(editor("abcdef", 2).window_start(4), 0);
+    }
+
+    #[test]
+    fn window_start_counts_wide_characters_twice() {
+        assert_eq!(editor("数据库", 3).window_start(7), 0);
+        assert_eq!(editor("数据库", 3).window_start(6), 1);
+        assert_eq!(editor("数据库", 3).window_start(4), 2);
+    }
+
+    #[test]
+    fn fit_does_not_split_wide_characters() {
+        assert_eq!(fit("abc".chars(), 2), "ab");
+        assert_eq!(fit("数据库".chars(), 5), "数据");
+        assert_eq!(fit("数据库".chars(), 1), "");
+    }
+
+    #[test]
+    fn word_start_skips_spaces_then_the_word() {
+        assert_eq!(editor("foo bar  ", 9).word_start(), 4);
+        assert_eq!(editor("foo bar", 5).word_start(), 4);
+        assert_eq!(editor("foo", 0).word_start(), 0);
+    }
+
+    #[test]
+    fn word_end_skips_spaces_then_the_word() {
+        assert_eq!(editor("  foo bar", 0).word_end(), 5);
+        assert_eq!(editor("foo bar", 3).word_end(), 7);
+        assert_eq!(editor("foo", 3).word_end(), 3);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_the_word_before_the_cursor() {
+        let mut line = editor("git commit", 10);
+        assert!(line.edit(key(KeyCode::Char('w'), KeyModifiers::CONTROL)));
+        assert_eq!((line.text(), line.cursor), (String::from("git "), 4));
+    }
+
+    #[test]
+    fn ctrl_w_at_the_start_does_nothing() {
+        let mut line = editor("git", 0);
+        assert!(line.edit(key(KeyCode::Char('w'), KeyModifiers::CONTROL)));
+        assert_eq!((line.text(), line.cursor), (String::from("git"), 0));
+    }
+
+    #[test]
+    fn alt_backspace_deletes_trailing_spaces_with_the_word() {
+        let mut line = editor("one two  ", 9);
+        assert!(line.edit(key(KeyCode::Backspace, KeyModifiers::ALT)));
+        assert_eq!((line.text(), line.cursor), (String::from("one "), 4));
+    }
+
+    #[test]
+    fn ctrl_u_and_ctrl_k_delete_around_the_cursor() {
+        let mut line = editor("left right", 5);
+        assert!(line.edit(key(KeyCode::Char('k'), KeyModifiers::CONTROL)));
+        assert_eq!((line.text(), line.cursor), (String::from("left "), 5));
+        line.cursor = 2;
+        assert!(line.edit(key(KeyCode::Char('u'), KeyModifiers::CONTROL)));
+        assert_eq!((line.text(), line.cursor), (String::from("ft "), 0));
+    }
+
+    #[test]
+    fn typing_inserts_at_the_cursor() {
+        let mut line = editor("ac", 1);
+        assert!(line.edit(key(KeyCode::Char('b'), KeyModifiers::NONE)));
+        assert_eq!((line.text(), line.cursor), (String::from("abc"), 2));
+        assert!(!line.edit(key(KeyCode::Char('x'), KeyModifiers::ALT)));
+        assert!(!line.edit(key(KeyCode::Enter, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn backspace_and_delete_stop_at_the_ends() {
+        let mut line = editor("ab", 0);
+        assert!(line.edit(key(KeyCode::Backspace, KeyModifiers::NONE)));
+        assert_eq!(line.text(), "ab");
+        line.cursor = 2;
+        assert!(line.edit(key(KeyCode::Delete, KeyModifiers::NONE)));
+        assert_eq!(line.text(), "ab");
+        assert!(line.edit(key(KeyCode::Backspace, KeyModifiers::NONE)));
+        assert_eq!((line.text(), line.cursor), (String::from("a"), 1));
+    }
+
+    #[test]
+    fn word_moves_with_ctrl_arrows() {
+        let mut line = editor("one two three", 13);
+        assert!(line.edit(key(KeyCode::Left, KeyModifiers::CONTROL)));
+        assert_eq!(line.cursor, 8);
+        assert!(line.edit(key(KeyCode::Left, KeyModifiers::ALT)));
+        assert_eq!(line.cursor, 4);
+        assert!(line.edit(key(KeyCode::Right, KeyModifiers::CONTROL)));
+        assert_eq!(line.cursor, 7);
+    }
+
+    #[test]
+    fn history_stops_at_the_oldest_entry_and_returns_to_the_draft() {
+        let mut input = Input::new(String::new());
+        input.history(vec![String::from("first"), String::from("second")]);
+        input.editor.set("draft");
+        assert!(!input.browse_history(false));
+        assert!(input.browse_history(true));
+        assert_eq!(input.editor.text(), "second");
+        assert!(input.browse_history(true));
+        assert_eq!(input.editor.text(), "first");
+        assert!(input.browse_history(true));
+        assert_eq!(input.editor.text(), "first");
+        assert!(input.browse_history(false));
+        assert_eq!(input.editor.text(), "second");
+        assert!(input.browse_history(false));
+        assert_eq!(input.editor.text(), "draft");
+        assert_eq!(input.history_index, None);
+        assert!(!input.browse_history(false));
+    }
+
+    #[test]
+    fn history_without_entries_keeps_the_text() {
+        let mut input = Input::new(String::new());
+        input.editor.set("draft");
+        assert!(!input.browse_history(true));
+        assert_eq!(input.editor.text(), "draft");
+    }
+}
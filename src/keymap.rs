@@ -0,0 +1,130 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Toggle,
+    Submit,
+    Cancel,
+    Interrupt,
+    Back,
+    PreviewUp,
+    PreviewDown,
+    PreviewPageUp,
+    PreviewPageDown,
+    Help,
+    Reload,
+    /// Highlights the option at this index and submits it.
+    Choose(usize),
+    Expand,
+    Collapse,
+    /// Moves to the column on the left of a grid.
+    Left,
+    /// Moves to the column on the right of a grid.
+    Right,
+    Sort,
+}
+
+impl Action {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Toggle => "toggle",
+            Action::Submit => "submit",
+            Action::Cancel => "cancel",
+            Action::Interrupt => "quit",
+            Action::Back => "back",
+            Action::PreviewUp => "scroll preview up",
+            Action::PreviewDown => "scroll preview down",
+            Action::PreviewPageUp => "preview page up",
+            Action::PreviewPageDown => "preview page down",
+            Action::Help => "help",
+            Action::Reload => "reload",
+            Action::Choose(_) => "choose",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Sort => "sort",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: KeyEvent,
+    pub action: Action,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers, action: Action) -> KeyBinding {
+        KeyBinding {
+            key: KeyEvent::new(code, modifiers),
+            action,
+        }
+    }
+
+    pub fn key_label(&self) -> String {
+        key_label(&self.key)
+    }
+}
+
+/// Short human readable name of a key, e.g. `ctrl-c` or `↑`.
+pub fn key_label(key: &KeyEvent) -> String {
+    let mut label = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("ctrl-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("alt-");
+    }
+    if key.modifiers.contains(KeyModifiers::SHIFT) {
+        label.push_str("shift-");
+    }
+    match key.code {
+        KeyCode::Up => label.push('↑'),
+        KeyCode::Down => label.push('↓'),
+        KeyCode::Left => label.push('←'),
+        KeyCode::Right => label.push('→'),
+        KeyCode::Enter => label.push_str("enter"),
+        KeyCode::Esc => label.push_str("esc"),
+        KeyCode::Tab => label.push_str("tab"),
+        KeyCode::BackTab => label.push_str("shift-tab"),
+        KeyCode::Backspace => label.push_str("backspace"),
+        KeyCode::Delete => label.push_str("del"),
+        KeyCode::Insert => label.push_str("ins"),
+        KeyCode::Home => label.push_str("home"),
+        KeyCode::End => label.push_str("end"),
+        KeyCode::PageUp => label.push_str("pgup"),
+        KeyCode::PageDown => label.push_str("pgdn"),
+        KeyCode::F(n) => label.push_str(&format!("f{}", n)),
+        KeyCode::Char(' ') => label.push_str("space"),
+        KeyCode::Char(c) => label.push(c),
+        KeyCode::Null => label.push_str("null"),
+    }
+    label
+}
+
+pub fn default_key_bindings() -> Vec<KeyBinding> {
+    vec![
+        KeyBinding::new(KeyCode::Up, KeyModifiers::NONE, Action::Up),
+        KeyBinding::new(KeyCode::Down, KeyModifiers::NONE, Action::Down),
+        KeyBinding::new(KeyCode::Enter, KeyModifiers::NONE, Action::Submit),
+        KeyBinding::new(KeyCode::Left, KeyModifiers::NONE, Action::Back),
+        KeyBinding::new(KeyCode::Esc, KeyModifiers::NONE, Action::Cancel),
+        KeyBinding::new(KeyCode::Char('c'), KeyModifiers::CONTROL, Action::Interrupt),
+        KeyBinding::new(KeyCode::Up, KeyModifiers::SHIFT, Action::PreviewUp),
+        KeyBinding::new(KeyCode::Down, KeyModifiers::SHIFT, Action::PreviewDown),
+        KeyBinding::new(KeyCode::PageUp, KeyModifiers::NONE, Action::PreviewPageUp),
+        KeyBinding::new(
+            KeyCode::PageDown,
+            KeyModifiers::NONE,
+            Action::PreviewPageDown,
+        ),
+        KeyBinding::new(KeyCode::Char('?'), KeyModifiers::NONE, Action::Help),
+        KeyBinding::new(KeyCode::Char('r'), KeyModifiers::CONTROL, Action::Reload),
+        KeyBinding::new(KeyCode::Tab, KeyModifiers::NONE, Action::Sort),
+    ]
+}
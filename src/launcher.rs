@@ -0,0 +1,99 @@
+use menu::{MenuLike, Status};
+use serde::Deserialize;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// A launcher menu entry: either a submenu with `items` or a leaf with a shell `command`.
+#[derive(Deserialize)]
+pub struct Entry {
+    #[serde(default)]
+    pub label: String,
+    pub title: Option<String>,
+    pub command: Option<String>,
+    #[serde(default)]
+    pub items: Vec<Entry>,
+}
+
+impl Entry {
+    pub fn load(path: &Path) -> Result<Entry, Box<dyn StdError>> {
+        let content = fs::read_to_string(path)?;
+        let root: Entry = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
+            _ => toml::from_str(&content)?,
+        };
+        root.validate()?;
+        Ok(root)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match (&self.command, self.items.is_empty()) {
+            (Some(_), false) => Err(format!("{} has both a command and items", self.label)),
+            (None, true) => Err(format!("{} has neither a command nor items", self.label)),
+            _ => self.items.iter().try_for_each(Entry::validate),
+        }
+    }
+}
+
+/// How the launcher ended.
+pub enum Launch {
+    /// The chosen command ran and exited with this code.
+    Exited(i32),
+    Cancelled,
+    Interrupted,
+}
+
+/// Builds the menu for `entry`, with a submenu for each of its items that has items itself.
+fn build(entry: &Entry, new_menu: &impl Fn() -> Box<dyn MenuLike>) -> Box<dyn MenuLike> {
+    let mut menu = new_menu();
+    menu.title(entry.title.clone().unwrap_or_else(|| entry.label.clone()));
+    menu.options(entry.items.iter().map(|item| item.label.clone()).collect());
+    for (index, item) in entry.items.iter().enumerate() {
+        if !item.items.is_empty() {
+            menu.submenu(index, build(item, new_menu));
+        }
+    }
+    menu
+}
+
+/// Shows `root`'s items, walking down into submenus until a command is chosen, then runs it.
+/// Esc goes back up a level and cancels at the top.
+pub fn run(
+    root: &Entry,
+    new_menu: impl Fn() -> Box<dyn MenuLike>,
+) -> Result<Launch, Box<dyn StdError>> {
+    let mut menu = build(root, &new_menu);
+    let outcome = menu.run_outcome()?;
+    let selected = outcome
+        .selected
+        .and_then(|selected| selected.into_iter().next());
+    let index = match (outcome.status, selected) {
+        (Status::Interrupted, _) => return Ok(Launch::Interrupted),
+        (Status::Submitted, Some(index)) => index,
+        _ => return Ok(Launch::Cancelled),
+    };
+    let entry = outcome
+        .path
+        .iter()
+        .fold(root, |entry, index| &entry.items[*index]);
+    match &entry.items[index].command {
+        Some(command) => Ok(Launch::Exited(execute(command)?)),
+        None => Ok(Launch::Cancelled),
+    }
+}
+
+fn execute(command: &str) -> Result<i32, Box<dyn StdError>> {
+    let status = Command::new("sh").arg("-c").arg(command).status()?;
+    if let Some(code) = status.code() {
+        return Ok(code);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return Ok(128 + signal);
+        }
+    }
+    Ok(1)
+}
@@ -1,15 +1,174 @@
 #[macro_use]
 extern crate crossterm;
+mod confirm;
+mod input;
+mod keymap;
+mod menu_bar;
+mod stream;
+mod table;
+mod tree;
+mod wizard;
+
+pub use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 pub use crossterm::style::Color;
+use crossterm::tty::IsTty;
 use crossterm::{
     cursor,
-    event::{read, Event, KeyCode, KeyEvent, KeyModifiers},
-    style::{Print, ResetColor, SetBackgroundColor, SetForegroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    event::{poll, read, Event},
+    style::{Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use std::error::Error as StdError;
-use std::io::{stdout, Write};
-use std::{collections::HashSet, process};
+use std::fs::{File, OpenOptions};
+use std::io::{self, stdin, stdout, BufWriter, Write};
+use std::{
+    collections::{HashMap, HashSet},
+    process,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+use unicode_width::UnicodeWidthStr;
+#[cfg(feature = "async")]
+use {
+    crossterm::event::EventStream,
+    futures::StreamExt,
+    std::{
+        future::{poll_fn, Future},
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+};
+
+pub use confirm::Confirm;
+pub use input::{Input, Validator};
+pub use keymap::{key_label, Action, KeyBinding};
+pub use menu_bar::MenuBar;
+use stream::OptionReceiver;
+pub use stream::{OptionSender, OptionUpdate};
+pub use table::{Align, Column, Table};
+pub use tree::{TreeMenu, TreeNode};
+pub use wizard::{Answer, Wizard};
+
+/// How often a menu with an option stream checks it for updates while waiting for keys.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const SECOND: Duration = Duration::from_secs(1);
+
+/// Renders the preview pane content for the highlighted option.
+pub type Preview = Box<dyn Fn(&str) -> String>;
+
+/// Called with the index and label of the option that became highlighted.
+pub type OnHighlight = Box<dyn FnMut(usize, &str)>;
+
+/// Called with the index and label of a toggled option and whether it is chosen now.
+pub type OnToggle = Box<dyn FnMut(usize, &str, bool)>;
+
+/// Called with the chosen options on submit. An error keeps the menu open and shows its
+/// message.
+pub type OnSubmit = Box<dyn FnMut(&HashSet<usize>) -> Result<(), String>>;
+
+/// Fetches fresh options on reload, `None` keeps the current ones.
+pub type Reload = Box<dyn Fn() -> Option<Vec<String>>>;
+
+/// The future returned by `MenuLike::run_async`.
+#[cfg(feature = "async")]
+pub type OutcomeFuture<'a> = Pin<Box<dyn Future<Output = Result<Outcome, Box<dyn StdError>>> + 'a>>;
+
+/// A submenu taken out of its parent while it runs, with the index of its option.
+type OpenedSubmenu = (usize, Box<dyn MenuLike>);
+
+/// Maximum height of an inline menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Height {
+    Rows(u16),
+    Percent(u16),
+}
+
+impl Height {
+    fn lines(&self, terminal_rows: u16) -> usize {
+        match self {
+            Height::Rows(rows) => *rows as usize,
+            Height::Percent(percent) => terminal_rows as usize * (*percent).min(100) as usize / 100,
+        }
+    }
+}
+
+/// What a menu answers when its timeout runs out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeoutAnswer {
+    /// Submits as if enter was pressed: the highlighted option, or the chosen ones of a
+    /// `MultiMenu`.
+    Highlighted,
+    /// Submits the option at this index.
+    Index(usize),
+    Cancel,
+}
+
+/// A row of the options that is not an option itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Row {
+    Header,
+    Separator,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Submitted,
+    Cancelled,
+    Interrupted,
+}
+
+/// How a menu ended, as returned by `MenuLike::run_outcome`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: Status,
+    /// The chosen options, `None` when cancelled or when nothing was chosen.
+    pub selected: Option<HashSet<usize>>,
+    /// The key that ended the menu, `None` for the line based fallback and on timeout.
+    pub key: Option<KeyEvent>,
+    /// Indices of the options that opened submenus, from the top menu down to the one that
+    /// ended, whose choice is in `selected`.
+    pub path: Vec<usize>,
+}
+
+impl Outcome {
+    pub fn new(status: Status, selected: Option<HashSet<usize>>, key: Option<KeyEvent>) -> Outcome {
+        Outcome {
+            status,
+            selected,
+            key,
+            path: Vec::new(),
+        }
+    }
+}
+
+/// Where a menu is drawn; anything that can be written to and asked whether it is a terminal.
+pub trait Writer: Write + IsTty {}
+
+impl<T: Write + IsTty> Writer for T {}
+
+/// The controlling terminal, buffered like stdout so a frame is written at once.
+struct Tty(BufWriter<File>);
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl IsTty for Tty {
+    fn is_tty(&self) -> bool {
+        self.0.get_ref().is_tty()
+    }
+}
 
 pub enum Direction {
     Up,
@@ -30,15 +189,62 @@ pub trait MenuLike {
     fn options(&mut self, options: Vec<String>) {
         let mut_menu = self.get_menu_mut();
         mut_menu.options = options;
+        mut_menu.rows.clear();
+        mut_menu.disabled.clear();
+        mut_menu.table = None;
+    }
+
+    /// Shows `table`'s rows as the options, lined up in columns under its header. Tab sorts the
+    /// rows by the next column. Reloaded or streamed options replace the table with plain labels.
+    fn table(&mut self, table: Table) {
+        self.options(table.format_rows());
+        let mut_menu = self.get_menu_mut();
+        mut_menu.table = Some(table);
+    }
+
+    /// Sorts the table's rows by `column`, descending when they already are sorted ascending by
+    /// it. The highlighted and chosen rows stay the same.
+    fn sort_by(&mut self, column: usize) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.sort_table(column);
+    }
+
+    /// Adds a header followed by its options after the current options. Headers are skipped
+    /// when moving through a `Menu`; in a `MultiMenu` toggling a header toggles its group.
+    fn group(&mut self, header: String, options: Vec<String>) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.rows.insert(mut_menu.options.len(), Row::Header);
+        mut_menu.options.push(header);
+        mut_menu.options.extend(options);
+    }
+
+    /// Shows the option at `index` dimmed and refuses to choose it, with `reason` shown while it
+    /// is highlighted.
+    fn disable(&mut self, index: usize, reason: Option<String>) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.disabled.insert(index, reason);
+    }
+
+    fn enable(&mut self, index: usize) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.disabled.remove(&index);
+    }
+
+    /// Adds a separator line after the current options, which also ends the last group.
+    fn separator(&mut self) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.rows.insert(mut_menu.options.len(), Row::Separator);
+        mut_menu.options.push(String::new());
     }
 
     fn selected_options(&mut self, selected_options: HashSet<usize>) {
         let mut_menu = self.get_menu_mut();
-        let options_len = mut_menu.options.len().clone();
+        let options_len = mut_menu.options.len();
         mut_menu.selected_options = selected_options;
+        let rows = &mut_menu.rows;
         mut_menu
             .selected_options
-            .retain(|index| *index < options_len);
+            .retain(|index| *index < options_len && !rows.contains_key(index));
     }
 
     fn selected_index(&mut self, selected_index: usize) {
@@ -65,6 +271,155 @@ pub trait MenuLike {
         mut_menu.selected_background_color = color;
     }
 
+    fn preview(&mut self, preview: Preview) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.preview = Some(preview);
+    }
+
+    fn preview_height(&mut self, preview_height: usize) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.preview_height = preview_height.max(1);
+    }
+
+    /// Binds `key` to `action`, replacing any previous binding of that key.
+    fn bind(&mut self, key: KeyEvent, action: Action) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.key_bindings.retain(|binding| binding.key != key);
+        mut_menu.key_bindings.push(KeyBinding { key, action });
+    }
+
+    /// Flows the options into as many columns as fit the terminal width, top to bottom and then
+    /// left to right. Left and right move between the columns instead of going back.
+    fn grid(&mut self, grid: bool) {
+        let left = KeyEvent::new(KeyCode::Left, KeyModifiers::NONE);
+        let right = KeyEvent::new(KeyCode::Right, KeyModifiers::NONE);
+        if grid {
+            self.bind(left, Action::Left);
+            self.bind(right, Action::Right);
+        } else {
+            self.bind(left, Action::Back);
+            self.get_menu_mut()
+                .key_bindings
+                .retain(|binding| binding.key != right);
+        }
+        let mut_menu = self.get_menu_mut();
+        mut_menu.grid = grid;
+        mut_menu.grid_columns = 1;
+    }
+
+    fn footer(&mut self, footer: bool) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.footer = footer;
+    }
+
+    /// Draws the menu on the alternate screen, using the whole terminal.
+    fn fullscreen(&mut self, fullscreen: bool) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.fullscreen = fullscreen;
+    }
+
+    /// Opens `submenu` when the option at `index` is submitted. The submenu's title is shown
+    /// after the titles of the menus above it, and it falls back to the option's label.
+    fn submenu(&mut self, index: usize, submenu: Box<dyn MenuLike>) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.submenus.insert(index, submenu);
+    }
+
+    /// Lets options be pushed, updated and removed while the menu runs, e.g. from another
+    /// thread. The title shows that options are loading until every sender is dropped.
+    fn option_stream(&mut self) -> OptionSender {
+        let mut_menu = self.get_menu_mut();
+        let (sender, receiver) = stream::channel();
+        mut_menu.option_receiver = Some(receiver);
+        sender
+    }
+
+    /// Replaces the options with the ones `reload` returns when ctrl-r is pressed, keeping the
+    /// highlighted and chosen options by their labels.
+    fn reload(&mut self, reload: Reload) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.reload = Some(reload);
+    }
+
+    /// Answers with `answer` when no key is pressed for `timeout`, counting down in the title.
+    /// Any key starts the countdown over.
+    fn timeout(&mut self, timeout: Duration, answer: TimeoutAnswer) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.timeout = Some((timeout, answer));
+    }
+
+    /// Calls `hook` whenever a key moves the highlight to another option.
+    fn on_highlight(&mut self, hook: OnHighlight) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.on_highlight = Some(hook);
+    }
+
+    /// Calls `hook` whenever an option of a `MultiMenu` is toggled.
+    fn on_toggle(&mut self, hook: OnToggle) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.on_toggle = Some(hook);
+    }
+
+    /// Calls `hook` before the menu is submitted, with enter or with an answer typed into the
+    /// line based fallback. When it rejects the choice the menu stays open and shows the reason
+    /// until the next key.
+    fn on_submit(&mut self, hook: OnSubmit) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.on_submit = Some(hook);
+    }
+
+    /// Where the menu is drawn, stdout by default.
+    fn writer(&mut self, writer: Box<dyn Writer>) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.writer = writer;
+    }
+
+    /// Draws the menu on the controlling terminal instead of stdout, so that stdout can be
+    /// redirected while the menu is shown. Keys are read from the terminal whenever stdin is
+    /// redirected.
+    fn tty(&mut self) -> Result<(), Box<dyn StdError>> {
+        #[cfg(windows)]
+        let path = "CONOUT$";
+        #[cfg(not(windows))]
+        let path = "/dev/tty";
+        let tty = OpenOptions::new().write(true).open(path)?;
+        self.writer(Box::new(Tty(BufWriter::new(tty))));
+        Ok(())
+    }
+
+    /// Whether `run` exits the process with code 1 on ctrl-c, which it does by default.
+    /// When disabled `run` returns `None` instead; `run_outcome` never exits.
+    fn exit_on_interrupt(&mut self, exit_on_interrupt: bool) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.exit_on_interrupt = exit_on_interrupt;
+    }
+
+    /// Limits the lines an inline menu takes, scrolling its options when they do not fit.
+    fn height(&mut self, height: Height) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.height = Some(height);
+    }
+
+    fn footer_foreground_color(&mut self, color: Color) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.footer_foreground_color = color;
+    }
+
+    fn header_foreground_color(&mut self, color: Color) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.header_foreground_color = color;
+    }
+
+    fn separator_foreground_color(&mut self, color: Color) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.separator_foreground_color = color;
+    }
+
+    fn status_foreground_color(&mut self, color: Color) {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.status_foreground_color = color;
+    }
+
     fn get_title(&self) -> &String {
         let menu = self.get_menu();
         &menu.title
@@ -75,6 +430,11 @@ pub trait MenuLike {
         &menu.options
     }
 
+    fn get_table(&self) -> Option<&Table> {
+        let menu = self.get_menu();
+        menu.table.as_ref()
+    }
+
     fn get_selected_options(&self) -> &HashSet<usize> {
         let menu = self.get_menu();
         &menu.selected_options
@@ -85,31 +445,111 @@ pub trait MenuLike {
         menu.selected_index
     }
 
+    fn get_key_bindings(&self) -> &Vec<KeyBinding> {
+        let menu = self.get_menu();
+        &menu.key_bindings
+    }
+
+    /// The bindings that currently do something, in the order they were bound.
+    fn get_active_key_bindings(&self) -> Vec<KeyBinding> {
+        let menu = self.get_menu();
+        menu.key_bindings
+            .iter()
+            .filter(|binding| match binding.action {
+                Action::PreviewUp
+                | Action::PreviewDown
+                | Action::PreviewPageUp
+                | Action::PreviewPageDown => menu.preview.is_some(),
+                Action::Back => !menu.breadcrumb.is_empty(),
+                Action::Reload => menu.reload.is_some(),
+                Action::Sort => menu.table.is_some(),
+                Action::Choose(index) => index < menu.options.len(),
+                _ => true,
+            })
+            .copied()
+            .collect()
+    }
+
     fn format_option(&self, index: usize) -> String {
         let menu = self.get_menu();
-        format!("{}", menu.options[index])
+        menu.options[index].to_string()
+    }
+
+    /// Describes a submitted answer, e.g. in a `Wizard`'s summary: the chosen options' labels.
+    fn format_answer(&self, selected: Option<&HashSet<usize>>) -> String {
+        let mut selected: Vec<usize> = selected.into_iter().flatten().copied().collect();
+        selected.sort_unstable();
+        let labels: Vec<String> = selected
+            .into_iter()
+            .map(|index| self.format_option(index))
+            .collect();
+        labels.join(", ")
     }
 
     fn format_title(&self) -> String {
         let menu = self.get_menu();
-        format!("{}\n", menu.title)
+        let mut title = menu.title.clone();
+        if !title.is_empty() && !menu.breadcrumb.is_empty() {
+            title = format!("{} › {}", menu.breadcrumb.join(" › "), title);
+        }
+        if menu.option_receiver.is_some() {
+            title.push_str(" (loading…)");
+        }
+        if let Some(seconds) = menu.seconds_left() {
+            title.push_str(&format!(" ({}s)", seconds));
+        }
+        let mut title = match title.trim_start() {
+            "" => String::new(),
+            title => format!("{}\n", title),
+        };
+        if let Some(table) = &menu.table {
+            title.push_str(&table.format_header(menu.selector_width()));
+            title.push('\n');
+        }
+        title
     }
 
     fn restore_console(&mut self) -> Result<(), Box<dyn StdError>> {
         let mut_menu = self.get_menu_mut();
         disable_raw_mode()?;
-        let dist = mut_menu.options.len() as u16 - mut_menu.selected_index as u16;
-        execute!(mut_menu.stdout, cursor::MoveToNextLine(dist), cursor::Show)?;
+        if mut_menu.fullscreen {
+            execute!(mut_menu.writer, LeaveAlternateScreen, cursor::Show)?;
+            return Ok(());
+        }
+        let dist = (mut_menu.drawn_lines - mut_menu.cursor_line) as u16;
+        execute!(mut_menu.writer, cursor::MoveToNextLine(dist), cursor::Show)?;
         Ok(())
     }
 
     fn setup_console(&mut self) -> Result<(), Box<dyn StdError>> {
+        let fixed_lines = self.format_title().lines().count()
+            + self.footer_lines().len()
+            + self.get_menu().preview_lines_len();
+        let (columns, rows) = terminal::size()?;
+        self.layout_grid(columns);
+        let lines = fixed_lines + self.option_lines_for(rows, fixed_lines);
         let mut_menu = self.get_menu_mut();
         enable_raw_mode()?;
-        execute!(mut_menu.stdout, cursor::Hide)?;
+        if mut_menu.fullscreen {
+            execute!(mut_menu.writer, EnterAlternateScreen, cursor::MoveTo(0, 0))?;
+        } else {
+            // make room below the cursor first, scrolling the terminal if the menu would not fit
+            queue!(
+                mut_menu.writer,
+                Print("\r\n".repeat(lines)),
+                cursor::MoveToPreviousLine(lines as u16)
+            )?;
+        }
+        execute!(mut_menu.writer, cursor::Hide)?;
         Ok(())
     }
 
+    /// How many lines the options take next to `fixed_lines` other lines on a terminal with
+    /// `rows` rows, which `setup_console` makes room for before the first frame.
+    fn option_lines_for(&self, rows: u16, fixed_lines: usize) -> usize {
+        self.get_menu().option_rows_for(rows, fixed_lines)
+    }
+
     fn move_with_direction(
         &mut self,
         direction: Direction,
@@ -120,153 +560,1006 @@ pub trait MenuLike {
         let selector = &mut_menu.selector;
         let selected_foreground_color = mut_menu.selected_foreground_color;
         let selected_background_color = mut_menu.selected_background_color;
-        let dist = mut_menu.selector.len() as u16;
+        let dist = mut_menu.selector_width() as u16;
         queue!(
-            mut_menu.stdout,
+            mut_menu.writer,
             Clear(ClearType::CurrentLine),
             cursor::MoveRight(dist),
             Print(current_line_out),
         )?;
         match direction {
             Direction::Up => {
-                queue!(mut_menu.stdout, cursor::MoveToPreviousLine(1))?;
+                queue!(mut_menu.writer, cursor::MoveToPreviousLine(1))?;
+            }
+            Direction::Down => {
+                queue!(mut_menu.writer, cursor::MoveToNextLine(1))?;
+            }
+        }
+        queue!(
+            mut_menu.writer,
+            Clear(ClearType::CurrentLine),
+            SetForegroundColor(selected_foreground_color),
+            SetBackgroundColor(selected_background_color),
+            Print(selector),
+            Print(next_line_out),
+            cursor::MoveToColumn(1),
+            ResetColor
+        )?;
+        Ok(())
+    }
+
+    /// Whether the row at `index` can be highlighted.
+    fn is_selectable(&self, index: usize) -> bool {
+        !self.get_menu().rows.contains_key(&index)
+    }
+
+    /// Moves the highlight off a header or separator, down if possible.
+    fn skip_unselectable(&mut self) {
+        let index = self.get_selected_index();
+        let len = self.get_options().len();
+        let selectable = (index..len)
+            .chain((0..index).rev())
+            .find(|index| self.is_selectable(*index));
+        if let Some(selectable) = selectable {
+            self.get_menu_mut().selected_index = selectable;
+        }
+    }
+
+    /// Moves the highlight up, staying in its column of a grid.
+    fn on_up_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.get_selected_index();
+        let top = index - self.get_menu().line_of(index);
+        let previous = (top..index).rev().find(|index| self.is_selectable(*index));
+        let mut_menu = self.get_menu_mut();
+        if let Some(previous) = previous {
+            let current_line_out = mut_menu.format_option(mut_menu.selected_index);
+            mut_menu.selected_index = previous;
+            let next_line_out = mut_menu.format_option(mut_menu.selected_index);
+            if !mut_menu.draws_incrementally() || !mut_menu.is_visible(mut_menu.selected_index) {
+                self.refresh_preview();
+                self.redraw()?;
+            } else {
+                mut_menu.cursor_line -= 1;
+                self.move_with_direction(Direction::Up, current_line_out, next_line_out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the highlight down, staying in its column of a grid.
+    fn on_down_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.get_selected_index();
+        let menu = self.get_menu();
+        let bottom = (index - menu.line_of(index) + menu.option_lines()).min(menu.options.len());
+        let next = (index + 1..bottom).find(|index| self.is_selectable(*index));
+        let mut_menu = self.get_menu_mut();
+        if let Some(next) = next {
+            let current_line_out = mut_menu.format_option(mut_menu.get_menu().selected_index);
+            mut_menu.selected_index = next;
+            let next_line_out = mut_menu.format_option(mut_menu.get_menu().selected_index);
+            if !mut_menu.draws_incrementally() || !mut_menu.is_visible(mut_menu.selected_index) {
+                self.refresh_preview();
+                self.redraw()?;
+            } else {
+                mut_menu.cursor_line += 1;
+                self.move_with_direction(Direction::Down, current_line_out, next_line_out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_space_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        Ok(())
+    }
+
+    fn on_preview_scroll(
+        &mut self,
+        direction: Direction,
+        lines: usize,
+    ) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        if mut_menu.preview.is_none() {
+            return Ok(());
+        }
+        let max_scroll = mut_menu
+            .preview_lines
+            .len()
+            .saturating_sub(mut_menu.preview_height);
+        let preview_scroll = match direction {
+            Direction::Up => mut_menu.preview_scroll.saturating_sub(lines),
+            Direction::Down => (mut_menu.preview_scroll + lines).min(max_scroll),
+        };
+        if preview_scroll != mut_menu.preview_scroll {
+            mut_menu.preview_scroll = preview_scroll;
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_help_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        mut_menu.show_help = !mut_menu.show_help;
+        self.redraw()
+    }
+
+    fn on_expand_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        Ok(())
+    }
+
+    fn on_collapse_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        Ok(())
+    }
+
+    /// Sorts the table by the column after the one it is sorted by, after sorting it both ways.
+    fn on_sort_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let column = match self.get_table() {
+            None => return Ok(()),
+            Some(table) => match table.get_sorted() {
+                None => 0,
+                Some((column, false)) => column,
+                Some((column, true)) => (column + 1) % table.get_columns().len(),
+            },
+        };
+        self.sort_by(column);
+        self.refresh_preview();
+        self.redraw()
+    }
+
+    /// Highlights the option beside the highlighted one in the column on the left of a grid.
+    fn on_left_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.get_selected_index();
+        let lines = self.get_menu().option_lines();
+        match index.checked_sub(lines) {
+            Some(left) if self.is_selectable(left) => {
+                self.get_menu_mut().selected_index = left;
+                self.refresh_preview();
+                self.redraw()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Highlights the option beside the highlighted one in the column on the right of a grid.
+    fn on_right_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let right = self.get_selected_index() + self.get_menu().option_lines();
+        if right < self.get_options().len() && self.is_selectable(right) {
+            self.get_menu_mut().selected_index = right;
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_reload_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        let options = match &mut_menu.reload {
+            Some(reload) => reload(),
+            None => None,
+        };
+        if let Some(options) = options {
+            mut_menu.replace_options(options);
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Handles every action except the ones that end the menu.
+    fn on_action(&mut self, action: Action) -> Result<(), Box<dyn StdError>> {
+        let preview_height = self.get_menu().preview_height;
+        match action {
+            Action::Up => self.on_up_key(),
+            Action::Down => self.on_down_key(),
+            Action::Toggle => self.on_space_key(),
+            Action::PreviewUp => self.on_preview_scroll(Direction::Up, 1),
+            Action::PreviewDown => self.on_preview_scroll(Direction::Down, 1),
+            Action::PreviewPageUp => self.on_preview_scroll(Direction::Up, preview_height),
+            Action::PreviewPageDown => self.on_preview_scroll(Direction::Down, preview_height),
+            Action::Help => self.on_help_key(),
+            Action::Reload => self.on_reload_key(),
+            Action::Expand => self.on_expand_key(),
+            Action::Collapse => self.on_collapse_key(),
+            Action::Left => self.on_left_key(),
+            Action::Right => self.on_right_key(),
+            Action::Sort => self.on_sort_key(),
+            Action::Submit
+            | Action::Choose(_)
+            | Action::Cancel
+            | Action::Interrupt
+            | Action::Back => Ok(()),
+        }
+    }
+
+    fn on_break(&mut self) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
+        if !self.is_selectable(self.get_selected_index()) || self.get_options().is_empty() {
+            return Ok(None);
+        }
+        let mut selected = HashSet::new();
+        selected.insert(self.get_selected_index());
+        Ok(Some(selected))
+    }
+
+    /// Applies the options received from the option stream and shows them.
+    fn on_option_updates(&mut self) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        let highlighted = mut_menu.options.get(mut_menu.selected_index).cloned();
+        if !mut_menu.receive_option_updates() {
+            return Ok(());
+        }
+        if mut_menu.options.get(mut_menu.selected_index) != highlighted.as_ref() {
+            self.refresh_preview();
+        }
+        self.redraw()
+    }
+
+    /// Runs while waiting for keys: applies option updates and counts the timeout down.
+    /// Returns the outcome once the timeout has run out.
+    fn on_tick(&mut self) -> Result<Option<Outcome>, Box<dyn StdError>> {
+        if self.get_menu().option_receiver.is_some() {
+            self.on_option_updates()?;
+        }
+        let mut_menu = self.get_menu_mut();
+        match mut_menu.seconds_left() {
+            Some(0) => {
+                self.restore_console()?;
+                return self.on_timeout().map(Some);
+            }
+            seconds if seconds != mut_menu.shown_seconds => self.redraw()?,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// The outcome of a menu whose timeout ran out. An answer the submit hook rejects cancels
+    /// the menu.
+    fn on_timeout(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        let answer = match self.get_menu().timeout {
+            Some((_, answer)) => answer,
+            None => TimeoutAnswer::Highlighted,
+        };
+        let (status, selected) = match answer {
+            TimeoutAnswer::Highlighted => {
+                let selected = self.on_break()?;
+                let menu = self.get_menu();
+                let selected = selected.map(|selected| {
+                    selected
+                        .into_iter()
+                        .filter(|index| !menu.disabled.contains_key(index))
+                        .collect::<HashSet<usize>>()
+                });
+                (
+                    Status::Submitted,
+                    selected.filter(|selected| !selected.is_empty()),
+                )
+            }
+            TimeoutAnswer::Index(index)
+                if index < self.get_options().len()
+                    && !self.get_menu().disabled.contains_key(&index) =>
+            {
+                (Status::Submitted, Some(HashSet::from([index])))
+            }
+            TimeoutAnswer::Index(_) => (Status::Submitted, None),
+            TimeoutAnswer::Cancel => (Status::Cancelled, None),
+        };
+        let mut_menu = self.get_menu_mut();
+        if status == Status::Submitted
+            && mut_menu
+                .check_submit(selected.clone().unwrap_or_default())
+                .is_err()
+        {
+            // nobody is there to read why, so the menu just ends without an answer
+            return Ok(Outcome::new(Status::Cancelled, None, None));
+        }
+        Ok(Outcome::new(status, selected, None))
+    }
+
+    /// Runs the preview callback for the highlighted option and resets the pane's scroll.
+    fn refresh_preview(&mut self) {
+        let mut_menu = self.get_menu_mut();
+        let highlighted = mut_menu.options.get(mut_menu.selected_index);
+        let preview_lines = match (&mut_menu.preview, highlighted) {
+            (Some(preview), Some(highlighted)) => preview(highlighted)
+                .lines()
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+            _ => Vec::new(),
+        };
+        mut_menu.preview_lines = preview_lines;
+        mut_menu.preview_scroll = 0;
+    }
+
+    /// Prints a single option on the current line, without moving to the next one.
+    fn draw_option(&mut self, index: usize) -> Result<(), Box<dyn StdError>> {
+        let option = self.format_option(index);
+        let mut_menu = self.get_menu_mut();
+        match mut_menu.rows.get(&index) {
+            Some(Row::Header) => {
+                queue!(
+                    mut_menu.writer,
+                    SetForegroundColor(mut_menu.header_foreground_color),
+                    SetAttribute(Attribute::Bold),
+                    Print(option),
+                    SetAttribute(Attribute::Reset),
+                    ResetColor,
+                )?;
+                return Ok(());
+            }
+            Some(Row::Separator) => return mut_menu.draw_separator(),
+            None => {}
+        }
+        if mut_menu.disabled.contains_key(&index) {
+            queue!(mut_menu.writer, SetAttribute(Attribute::Dim))?;
+        }
+        if index == mut_menu.selected_index {
+            queue!(
+                mut_menu.writer,
+                SetForegroundColor(mut_menu.selected_foreground_color),
+                SetBackgroundColor(mut_menu.selected_background_color),
+                Print(&mut_menu.selector),
+                Print(option),
+                ResetColor,
+            )?;
+        } else {
+            let dist = mut_menu.selector_width() as u16;
+            queue!(mut_menu.writer, cursor::MoveRight(dist), Print(option))?;
+        }
+        queue!(mut_menu.writer, SetAttribute(Attribute::Reset))?;
+        Ok(())
+    }
+
+    /// Prints the bordered preview pane below the options and returns the number of lines used.
+    fn draw_preview(&mut self) -> Result<usize, Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        if mut_menu.preview.is_none() {
+            return Ok(0);
+        }
+        let (columns, _) = terminal::size()?;
+        let inner_width = (columns as usize).saturating_sub(4);
+        let total = mut_menu.preview_lines.len();
+        let position = if total > mut_menu.preview_height {
+            format!(
+                " {}/{} ",
+                (mut_menu.preview_scroll + mut_menu.preview_height).min(total),
+                total
+            )
+        } else {
+            String::new()
+        };
+        let top_fill = (inner_width + 2).saturating_sub(position.chars().count() + 1);
+        queue!(
+            mut_menu.writer,
+            Clear(ClearType::CurrentLine),
+            Print("┌"),
+            Print("─".repeat(top_fill)),
+            Print(position),
+            Print("─┐\r\n"),
+        )?;
+        for row in 0..mut_menu.preview_height {
+            let line: String = mut_menu
+                .preview_lines
+                .get(mut_menu.preview_scroll + row)
+                .map(|line| line.chars().take(inner_width).collect())
+                .unwrap_or_default();
+            let padding = inner_width - line.chars().count();
+            queue!(
+                mut_menu.writer,
+                Clear(ClearType::CurrentLine),
+                Print("│ "),
+                Print(line),
+                Print(" ".repeat(padding)),
+                Print(" │\r\n"),
+            )?;
+        }
+        queue!(
+            mut_menu.writer,
+            Clear(ClearType::CurrentLine),
+            Print("└"),
+            Print("─".repeat(inner_width + 2)),
+            Print("┘\r\n"),
+        )?;
+        Ok(mut_menu.preview_height + 2)
+    }
+
+    /// What `action` does, as the footer and the help show it. Choosing an option is described
+    /// by the option's label.
+    fn describe(&self, action: Action) -> String {
+        match action {
+            Action::Choose(index) if index < self.get_options().len() => {
+                self.get_options()[index].clone()
+            }
+            action => action.description().to_string(),
+        }
+    }
+
+    /// The key hints, or every binding while help is shown.
+    fn footer_lines(&self) -> Vec<String> {
+        let bindings = self.get_active_key_bindings();
+        let menu = self.get_menu();
+        let mut lines = Vec::new();
+        if menu.show_help {
+            let key_width = bindings
+                .iter()
+                .map(|binding| binding.key_label().chars().count())
+                .max()
+                .unwrap_or(0);
+            for binding in &bindings {
+                let label = binding.key_label();
+                let padding = key_width - label.chars().count();
+                lines.push(format!(
+                    "  {}{}  {}",
+                    " ".repeat(padding),
+                    label,
+                    self.describe(binding.action)
+                ));
+            }
+        } else if menu.footer {
+            let mut hints: Vec<String> = Vec::new();
+            for binding in &bindings {
+                match binding.action {
+                    Action::PreviewUp
+                    | Action::PreviewDown
+                    | Action::PreviewPageUp
+                    | Action::PreviewPageDown
+                    | Action::Interrupt => continue,
+                    _ => hints.push(format!(
+                        "{} {}",
+                        binding.key_label(),
+                        self.describe(binding.action)
+                    )),
+                }
+            }
+            lines.push(hints.join("  "));
+        }
+        lines
+    }
+
+    /// Works out how many columns of options fit on a terminal `columns` wide, keeping the
+    /// columns as even as possible.
+    fn layout_grid(&mut self, columns: u16) {
+        if !self.get_menu().grid {
+            return;
+        }
+        let width = (0..self.get_options().len())
+            .map(|index| self.format_option(index).chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut_menu = self.get_menu_mut();
+        let options_len = mut_menu.options.len().max(1);
+        mut_menu.cell_width = mut_menu.selector_width() + width + 2;
+        let grid_columns = (columns as usize / mut_menu.cell_width).clamp(1, options_len);
+        let lines = options_len.div_ceil(grid_columns);
+        mut_menu.grid_columns = options_len.div_ceil(lines);
+    }
+
+    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
+        let title = self.format_title();
+        let footer_lines = self.footer_lines();
+        let (columns, rows) = terminal::size()?;
+        self.layout_grid(columns);
+        let mut_menu = self.get_menu_mut();
+        let title_lines = title.lines().count();
+        let status_lines = mut_menu.status.is_some() as usize;
+        let fixed_lines =
+            title_lines + mut_menu.preview_lines_len() + status_lines + footer_lines.len();
+        let options_len = mut_menu.options.len();
+        let option_lines = mut_menu.option_lines();
+        let grid_columns = mut_menu.grid_columns;
+        let cell_width = mut_menu.cell_width;
+        let selected_line = mut_menu.line_of(mut_menu.selected_index);
+        let option_rows = mut_menu.option_rows_for(rows, fixed_lines);
+        mut_menu.scroll_to(selected_line, option_rows);
+
+        for line in title.lines() {
+            queue!(
+                mut_menu.writer,
+                Clear(ClearType::CurrentLine),
+                Print(line),
+                Print("\r\n")
+            )?;
+        }
+        let scroll_offset = mut_menu.scroll_offset;
+        let visible_end = (scroll_offset + option_rows).min(option_lines);
+        for line in scroll_offset..visible_end {
+            queue!(self.get_menu_mut().writer, Clear(ClearType::CurrentLine))?;
+            for column in 0..grid_columns {
+                let index = column * option_lines + line;
+                if index >= options_len {
+                    break;
+                }
+                if column > 0 {
+                    let x = (column * cell_width + 1) as u16;
+                    queue!(self.get_menu_mut().writer, cursor::MoveToColumn(x))?;
+                }
+                self.draw_option(index)?;
+            }
+            queue!(self.get_menu_mut().writer, Print("\r\n"))?;
+        }
+        let preview_lines = self.draw_preview()?;
+        let mut_menu = self.get_menu_mut();
+        mut_menu.draw_status_and_footer(&footer_lines, columns)?;
+        let below_lines = preview_lines + status_lines + footer_lines.len();
+        let dist = (visible_end - selected_line + below_lines) as u16;
+        queue!(
+            mut_menu.writer,
+            Clear(ClearType::FromCursorDown),
+            cursor::MoveToPreviousLine(dist)
+        )?;
+        mut_menu.drawn_lines = title_lines + (visible_end - scroll_offset) + below_lines;
+        mut_menu.cursor_line = title_lines + selected_line - scroll_offset;
+        mut_menu.shown_seconds = mut_menu.seconds_left();
+        mut_menu.writer.flush()?;
+        Ok(())
+    }
+
+    /// Moves back to the first line of the menu and displays it again.
+    fn redraw(&mut self) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.get_menu_mut();
+        queue!(
+            mut_menu.writer,
+            cursor::MoveToPreviousLine(mut_menu.cursor_line as u16),
+            cursor::MoveToColumn(1)
+        )?;
+        self.display()
+    }
+
+    /// Whether more than one option can be chosen.
+    fn is_multi_select(&self) -> bool {
+        false
+    }
+
+    /// Whether the menu can be drawn: its writer is a terminal and keys can be read from stdin
+    /// or, when stdin is redirected, from the controlling terminal.
+    fn is_interactive(&self) -> bool {
+        let menu = self.get_menu();
+        menu.writer.is_tty() && (stdin().is_tty() || File::open("/dev/tty").is_ok())
+    }
+
+    /// Parses a line based answer: 1-based indices (comma separated for multi select) or labels.
+    fn parse_answer(&self, answer: &str) -> Result<HashSet<usize>, String> {
+        let menu = self.get_menu();
+        let is_option = |index: &usize| !menu.rows.contains_key(index);
+        let parts: Vec<&str> = if self.is_multi_select() {
+            answer.split(',').map(str::trim).collect()
+        } else {
+            vec![answer.trim()]
+        };
+        let mut selected = HashSet::new();
+        for part in parts.into_iter().filter(|part| !part.is_empty()) {
+            let index = match part.parse::<usize>() {
+                Ok(number) if number >= 1 && number <= menu.options.len() => {
+                    if !is_option(&(number - 1)) {
+                        return Err(format!("{} is not an option", number));
+                    }
+                    number - 1
+                }
+                Ok(number) => {
+                    return Err(format!(
+                        "{} is not between 1 and {}",
+                        number,
+                        menu.options.len()
+                    ))
+                }
+                Err(_) => match (0..menu.options.len())
+                    .find(|index| is_option(index) && menu.options[*index] == part)
+                {
+                    Some(index) => index,
+                    None => return Err(format!("{} is not an option", part)),
+                },
+            };
+            selected.insert(index);
+        }
+        Ok(selected)
+    }
+
+    /// Prints a numbered list and reads the answer as a line, for when the menu cannot be drawn.
+    fn run_fallback(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        run_line_fallback(self)
+    }
+
+    /// Turns the options answered in the line based fallback into what the menu submits.
+    fn on_answer(
+        &mut self,
+        answer: HashSet<usize>,
+    ) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
+        Ok(Some(answer).filter(|answer| !answer.is_empty()))
+    }
+
+    fn run(&mut self) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
+        let outcome = self.run_outcome()?;
+        self.get_menu().exit_if_interrupted(&outcome);
+        Ok(outcome.selected)
+    }
+
+    /// Like `run`, but also tells how the menu ended. Submitting an option that has a submenu
+    /// runs the submenu in place of this menu; leaving the submenu with esc or left shows this
+    /// menu again.
+    fn run_outcome(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        loop {
+            let outcome = self.run_menu()?;
+            let (index, mut submenu) = match self.get_menu_mut().open_submenu(&outcome)? {
+                Some(submenu) => submenu,
+                None => return Ok(outcome),
+            };
+            let submenu_outcome = submenu.run_outcome();
+            let mut_menu = self.get_menu_mut();
+            if let Some(outcome) = mut_menu.close_submenu(index, submenu, submenu_outcome)? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Like `run_outcome`, but reads keys from crossterm's `EventStream` so that waiting for
+    /// them does not block the executor. Dropping the future restores the console.
+    /// The line based fallback still reads stdin blocking.
+    #[cfg(feature = "async")]
+    fn run_async(&mut self) -> OutcomeFuture<'_> {
+        Box::pin(async move {
+            loop {
+                let outcome = self.run_menu_async().await?;
+                let (index, mut submenu) = match self.get_menu_mut().open_submenu(&outcome)? {
+                    Some(submenu) => submenu,
+                    None => return Ok(outcome),
+                };
+                let submenu_outcome = submenu.run_async().await;
+                let mut_menu = self.get_menu_mut();
+                if let Some(outcome) = mut_menu.close_submenu(index, submenu, submenu_outcome)? {
+                    return Ok(outcome);
+                }
+            }
+        })
+    }
+
+    /// Runs this menu on its own, without descending into submenus.
+    fn run_menu(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        if !self.is_interactive() {
+            return self.run_fallback();
+        }
+        self.start_menu()?;
+        loop {
+            if let Some(outcome) = self.on_tick()? {
+                return Ok(outcome);
+            }
+            if let Some(interval) = self.get_menu().tick_interval() {
+                if !poll(interval)? {
+                    continue;
+                }
+            }
+            if let Some(outcome) = self.handle_event(read()?)? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn run_menu_async(&mut self) -> OutcomeFuture<'_> {
+        Box::pin(async move {
+            if !self.is_interactive() {
+                return self.run_fallback();
+            }
+            let mut events = EventStream::new();
+            self.start_menu()?;
+            let mut running = Running::new(self);
+            let mut timer: Option<Timer> = None;
+            loop {
+                if let Some(outcome) = running.menu.on_tick()? {
+                    running.finish();
+                    return Ok(outcome);
+                }
+                let tick = running.menu.get_menu().next_tick();
+                if tick.is_some() && timer.is_none() {
+                    timer = Some(Timer::new());
+                }
+                let next = poll_fn(|cx| {
+                    if let (Some(tick), Some(timer)) = (tick, &timer) {
+                        if timer.poll_until(tick, cx).is_ready() {
+                            return Poll::Ready(None);
+                        }
+                    }
+                    if let Some(receiver) = &mut running.menu.get_menu_mut().option_receiver {
+                        if receiver.poll_ready(cx).is_ready() {
+                            return Poll::Ready(None);
+                        }
+                    }
+                    events.poll_next_unpin(cx).map(Some)
+                });
+                let event = match next.await {
+                    None => continue,
+                    Some(Some(event)) => event?,
+                    Some(None) => return Err("the event stream ended".into()),
+                };
+                if let Some(outcome) = running.menu.handle_event(event)? {
+                    running.finish();
+                    return Ok(outcome);
+                }
+            }
+        })
+    }
+
+    /// Sets the console up and shows the menu.
+    fn start_menu(&mut self) -> Result<(), Box<dyn StdError>> {
+        self.get_menu_mut().restart_timeout();
+        self.skip_unselectable();
+        let mut_menu = self.get_menu_mut();
+        mut_menu.status = mut_menu.disabled_reason();
+        self.setup_console()?;
+        self.refresh_preview();
+        self.display()
+    }
+
+    /// Reacts to a terminal event, returning the outcome once the menu has ended.
+    fn handle_event(&mut self, event: Event) -> Result<Option<Outcome>, Box<dyn StdError>> {
+        let key = match event {
+            Event::Key(key) => {
+                self.get_menu_mut().restart_timeout();
+                key
+            }
+            Event::Resize(_, _) => {
+                self.redraw()?;
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+        let action = match self
+            .get_key_bindings()
+            .iter()
+            .find(|binding| binding.key == key)
+        {
+            Some(binding) => binding.action,
+            None => return Ok(None),
+        };
+        let mut_menu = self.get_menu_mut();
+        if matches!(action, Action::Choose(index) if index >= mut_menu.options.len()) {
+            return Ok(None);
+        }
+        let previous_status = mut_menu.status.take();
+        let highlighted = mut_menu.selected_index;
+        if let Action::Choose(index) = action {
+            mut_menu.selected_index = index;
+        }
+        match action {
+            Action::Submit | Action::Choose(_) => {
+                if self.get_selected_index() != highlighted {
+                    self.redraw()?;
+                }
+                let selected = self.on_break()?;
+                let mut_menu = self.get_menu_mut();
+                let checked = mut_menu.check_submit(selected.clone().unwrap_or_default());
+                if let Err(message) = checked {
+                    mut_menu.status = Some(message);
+                    self.redraw()?;
+                    return Ok(None);
+                }
+                self.restore_console()?;
+                return Ok(Some(Outcome::new(Status::Submitted, selected, Some(key))));
             }
-            Direction::Down => {
-                queue!(mut_menu.stdout, cursor::MoveToNextLine(1))?;
+            Action::Cancel => {
+                self.restore_console()?;
+                return Ok(Some(Outcome::new(Status::Cancelled, None, Some(key))));
+            }
+            Action::Back if !self.get_menu().breadcrumb.is_empty() => {
+                self.restore_console()?;
+                return Ok(Some(Outcome::new(Status::Cancelled, None, Some(key))));
+            }
+            Action::Interrupt => {
+                self.restore_console()?;
+                return Ok(Some(Outcome::new(Status::Interrupted, None, Some(key))));
             }
+            action => self.on_action(action)?,
         }
-        queue!(
-            mut_menu.stdout,
-            Clear(ClearType::CurrentLine),
-            SetForegroundColor(selected_foreground_color),
-            SetBackgroundColor(selected_background_color),
-            Print(selector),
-            Print(next_line_out),
-            cursor::MoveToColumn(1),
-            ResetColor
-        )?;
-        Ok(())
-    }
-
-    fn on_up_key(&mut self) -> Result<(), Box<dyn StdError>> {
         let mut_menu = self.get_menu_mut();
-        if mut_menu.selected_index > 0 {
-            let current_line_out = mut_menu.format_option(mut_menu.selected_index);
-            mut_menu.selected_index -= 1;
-            let next_line_out = mut_menu.format_option(mut_menu.selected_index);
-            self.move_with_direction(Direction::Up, current_line_out, next_line_out)?;
+        if mut_menu.selected_index != highlighted {
+            mut_menu.call_highlight_hook();
         }
-        Ok(())
+        if mut_menu.status.is_none() {
+            mut_menu.status = mut_menu.disabled_reason();
+        }
+        if mut_menu.status != previous_status {
+            self.redraw()?;
+        }
+        self.get_menu_mut().writer.flush()?;
+        Ok(None)
     }
+}
 
-    fn on_down_key(&mut self) -> Result<(), Box<dyn StdError>> {
-        let mut_menu = self.get_menu_mut();
-        if mut_menu.selected_index < mut_menu.options.len() - 1 {
-            let current_line_out = mut_menu.format_option(mut_menu.get_menu().selected_index);
-            mut_menu.selected_index += 1;
-            let next_line_out = mut_menu.format_option(mut_menu.get_menu().selected_index);
-            self.move_with_direction(Direction::Down, current_line_out, next_line_out)?;
+/// Restores the console if a running menu is dropped before it ends.
+#[cfg(feature = "async")]
+struct Running<'a, M: MenuLike + ?Sized> {
+    menu: &'a mut M,
+    finished: bool,
+}
+
+#[cfg(feature = "async")]
+impl<'a, M: MenuLike + ?Sized> Running<'a, M> {
+    fn new(menu: &'a mut M) -> Self {
+        Running {
+            menu,
+            finished: false,
         }
-        Ok(())
     }
 
-    fn on_space_key(&mut self) -> Result<(), Box<dyn StdError>> {
-        Ok(())
+    fn finish(&mut self) {
+        self.finished = true;
     }
+}
 
-    fn on_break(&mut self) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
-        let mut selected = HashSet::new();
-        selected.insert(self.get_selected_index());
-        Ok(Some(selected))
+#[cfg(feature = "async")]
+impl<M: MenuLike + ?Sized> Drop for Running<'_, M> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.menu.restore_console();
+        }
     }
-    
-    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
-        let mut_menu = self.get_menu_mut();
+}
 
-        let title = mut_menu.format_title();
-        print!("{}", title);
-        for i in 0..mut_menu.options.len() {
-            let option = mut_menu.format_option(i);
-            if i == mut_menu.selected_index {
-                let selector = &mut_menu.selector;
-                let selected_foreground_color = mut_menu.selected_foreground_color;
-                let selected_background_color = mut_menu.selected_background_color;
-                queue!(
-                    mut_menu.stdout,
-                    SetForegroundColor(selected_foreground_color),
-                    SetBackgroundColor(selected_background_color),
-                    Print(selector),
-                    Print(option),
-                    ResetColor,
-                    Print("\n"),
-                )?;
-                continue;
+/// Wakes a task at an instant from one thread, so that no particular async runtime is needed.
+/// The thread ends when the timer is dropped.
+#[cfg(feature = "async")]
+struct Timer {
+    sender: mpsc::Sender<(Instant, Waker)>,
+}
+
+#[cfg(feature = "async")]
+impl Timer {
+    fn new() -> Self {
+        let (sender, receiver) = mpsc::channel::<(Instant, Waker)>();
+        thread::spawn(move || {
+            // only the latest request counts, the task asks again whenever it is polled
+            let mut next: Option<(Instant, Waker)> = None;
+            loop {
+                let received = match &next {
+                    Some((until, _)) => {
+                        receiver.recv_timeout(until.saturating_duration_since(Instant::now()))
+                    }
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(request) => next = Some(request),
+                    Err(RecvTimeoutError::Timeout) => {
+                        if let Some((_, waker)) = next.take() {
+                            waker.wake();
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
             }
-            let dist = mut_menu.selector.len() as u16;
-            queue!(
-                mut_menu.stdout,
-                cursor::MoveRight(dist),
-                Print(option),
-                Print("\n"),
-            )?;
+        });
+        Timer { sender }
+    }
+
+    /// Ready once `until` has passed, otherwise wakes the task then.
+    fn poll_until(&self, until: Instant, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= until {
+            return Poll::Ready(());
         }
-        let dist = (mut_menu.options.len() - mut_menu.selected_index) as u16;
-        queue!(mut_menu.stdout, cursor::MoveToPreviousLine(dist))?;
-        mut_menu.stdout.flush()?;
-        Ok(())
+        // the thread only stops when the timer is dropped, so the send cannot fail
+        let _ = self.sender.send((until, cx.waker().clone()));
+        Poll::Pending
     }
+}
 
-    fn run(&mut self) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
-        self.setup_console()?;
-        self.display()?;
+/// The body of `MenuLike::run_fallback`, so that components overriding it can still use it.
+fn run_line_fallback<M: MenuLike + ?Sized>(menu: &mut M) -> Result<Outcome, Box<dyn StdError>> {
+    menu.get_menu_mut().restart_timeout();
+    menu.skip_unselectable();
+    let mut title = menu.format_title();
+    if let Some(table) = menu.get_table() {
+        // line the header up with the numbered options instead of the selector
+        let mut lines: Vec<String> = title.lines().map(String::from).collect();
+        lines.pop();
+        lines.push(table.format_header(5));
+        title = lines.iter().map(|line| format!("{}\n", line)).collect();
+    }
+    let multi_select = menu.is_multi_select();
+    let default = match multi_select {
+        true => menu.get_selected_options().clone(),
+        false => HashSet::from([menu.get_selected_index()]),
+    };
+    let mut default_numbers: Vec<usize> = default.iter().map(|index| index + 1).collect();
+    default_numbers.sort_unstable();
+    let default_numbers: Vec<String> = default_numbers
+        .iter()
+        .map(|number| number.to_string())
+        .collect();
 
-        loop {
-            match read()? {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Up,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    self.on_up_key()?;
-                }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Down,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    self.on_down_key()?;
-                }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char(' '),
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    self.on_space_key()?;
+    let options: Vec<String> = (0..menu.get_options().len())
+        .map(|index| menu.format_option(index))
+        .collect();
+    let mut_menu = menu.get_menu_mut();
+    write!(mut_menu.writer, "{}", title)?;
+    for (index, option) in options.iter().enumerate() {
+        match mut_menu.rows.get(&index) {
+            Some(Row::Header) => writeln!(mut_menu.writer, "{}", option)?,
+            Some(Row::Separator) => writeln!(mut_menu.writer)?,
+            None => match mut_menu.disabled.get(&index) {
+                Some(Some(reason)) => {
+                    writeln!(mut_menu.writer, "{:>3}) {} ({})", index + 1, option, reason)?
                 }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Enter,
-                    modifiers: KeyModifiers::NONE,
-                }) => break,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Esc,
-                    modifiers: KeyModifiers::NONE,
-                }) => {
-                    self.restore_console()?;
-                    return Ok(None);
-                }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('c'),
-                    modifiers: KeyModifiers::CONTROL,
-                }) => {
-                    self.restore_console()?;
-                    process::exit(1);
+                Some(None) => writeln!(mut_menu.writer, "{:>3}) {} (disabled)", index + 1, option)?,
+                None => writeln!(mut_menu.writer, "{:>3}) {}", index + 1, option)?,
+            },
+        }
+    }
+    let mut lines = None;
+    loop {
+        let mut_menu = menu.get_menu_mut();
+        if multi_select {
+            write!(mut_menu.writer, "Select options, separated by commas")?;
+        } else {
+            write!(mut_menu.writer, "Select an option")?;
+        }
+        write!(mut_menu.writer, " [{}]: ", default_numbers.join(","))?;
+        mut_menu.writer.flush()?;
+
+        let answer = match mut_menu.deadline {
+            Some(deadline) => {
+                let lines = lines.get_or_insert_with(read_lines);
+                match lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(line) => line?,
+                    Err(RecvTimeoutError::Timeout) => {
+                        writeln!(mut_menu.writer)?;
+                        return menu.on_timeout();
+                    }
+                    Err(RecvTimeoutError::Disconnected) => String::new(),
                 }
-                _ => continue,
             }
-            self.get_menu_mut().stdout.flush()?;
+            None => {
+                let mut answer = String::new();
+                stdin().read_line(&mut answer)?;
+                answer
+            }
+        };
+        if answer.is_empty() {
+            writeln!(mut_menu.writer)?;
+            return Ok(Outcome::new(Status::Cancelled, None, None));
+        }
+        let selected = match answer.trim().is_empty() {
+            true => Ok(default.clone()),
+            false => menu.parse_answer(&answer),
+        };
+        let selected = match selected {
+            Ok(selected) => menu.on_answer(selected)?,
+            Err(message) => {
+                writeln!(menu.get_menu_mut().writer, "{}", message)?;
+                continue;
+            }
+        };
+        let mut_menu = menu.get_menu_mut();
+        match mut_menu.check_submit(selected.clone().unwrap_or_default()) {
+            Ok(_) => return Ok(Outcome::new(Status::Submitted, selected, None)),
+            Err(message) => writeln!(mut_menu.writer, "{}", message)?,
         }
-        self.restore_console()?;
-        self.on_break()
     }
 }
 
+/// Moves the entries of `map` after a removed option up by one, dropping the removed one.
+fn shift_removed<V: Clone>(map: &HashMap<usize, V>, removed: usize) -> HashMap<usize, V> {
+    map.iter()
+        .filter(|(index, _)| **index != removed)
+        .map(|(index, value)| match *index > removed {
+            true => (index - 1, value.clone()),
+            false => (*index, value.clone()),
+        })
+        .collect()
+}
+
+/// Reads stdin line by line on another thread, so that waiting for a line can time out.
+/// The thread is left blocked on stdin if the menu stops waiting.
+fn read_lines() -> Receiver<io::Result<String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        let mut line = String::new();
+        match stdin().read_line(&mut line) {
+            Ok(0) => return,
+            read => {
+                if sender.send(read.map(|_| line)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    receiver
+}
+
 impl MenuLike for Menu {
     fn get_menu_mut(&mut self) -> &mut Menu {
         self
@@ -279,13 +1572,50 @@ impl MenuLike for Menu {
 pub struct Menu {
     title: String,
     options: Vec<String>,
+    rows: HashMap<usize, Row>,
+    disabled: HashMap<usize, Option<String>>,
+    table: Option<Table>,
     selected_options: HashSet<usize>,
     selected_index: usize,
-    stdout: std::io::Stdout,
+    writer: Box<dyn Writer>,
     new_line_count: usize,
     selector: String,
     selected_foreground_color: Color,
     selected_background_color: Color,
+    preview: Option<Preview>,
+    preview_height: usize,
+    preview_lines: Vec<String>,
+    preview_scroll: usize,
+    reload: Option<Reload>,
+    key_bindings: Vec<KeyBinding>,
+    footer: bool,
+    footer_foreground_color: Color,
+    header_foreground_color: Color,
+    separator_foreground_color: Color,
+    status: Option<String>,
+    status_foreground_color: Color,
+    on_highlight: Option<OnHighlight>,
+    on_toggle: Option<OnToggle>,
+    on_submit: Option<OnSubmit>,
+    show_help: bool,
+    submenus: HashMap<usize, Box<dyn MenuLike>>,
+    breadcrumb: Vec<String>,
+    option_receiver: Option<OptionReceiver>,
+    timeout: Option<(Duration, TimeoutAnswer)>,
+    deadline: Option<Instant>,
+    shown_seconds: Option<u64>,
+    exit_on_interrupt: bool,
+    fullscreen: bool,
+    grid: bool,
+    /// Columns of the grid as last laid out, 1 when the options are a plain list.
+    grid_columns: usize,
+    /// Width of a grid column, selector included.
+    cell_width: usize,
+    height: Option<Height>,
+    scroll_offset: usize,
+    option_rows: usize,
+    drawn_lines: usize,
+    cursor_line: usize,
 }
 
 impl Menu {
@@ -310,22 +1640,471 @@ impl Menu {
     }
 }
 
+impl Menu {
+    /// Applies everything waiting in the option stream, dropping the stream once it is closed.
+    /// Returns whether anything changed.
+    fn receive_option_updates(&mut self) -> bool {
+        let mut changed = false;
+        while let Some(receiver) = &mut self.option_receiver {
+            match receiver.try_recv() {
+                Ok(update) => self.apply_option_update(update),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.option_receiver = None,
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Changes the options while keeping the same option highlighted, where it still exists.
+    fn apply_option_update(&mut self, update: OptionUpdate) {
+        // streamed options are plain labels, not rows of the table
+        self.table = None;
+        match update {
+            OptionUpdate::Push(option) => self.options.push(option),
+            OptionUpdate::Update(index, option) => {
+                if let Some(current) = self.options.get_mut(index) {
+                    *current = option;
+                }
+            }
+            OptionUpdate::Remove(index) => {
+                if index >= self.options.len() {
+                    return;
+                }
+                self.options.remove(index);
+                self.rows = shift_removed(&self.rows, index);
+                self.disabled = shift_removed(&self.disabled, index);
+                self.selected_options = self
+                    .selected_options
+                    .iter()
+                    .filter(|selected| **selected != index)
+                    .map(|selected| match *selected > index {
+                        true => selected - 1,
+                        false => *selected,
+                    })
+                    .collect();
+                if self.selected_index > index {
+                    self.selected_index -= 1;
+                }
+                self.selected_index = self
+                    .selected_index
+                    .min(self.options.len().saturating_sub(1));
+            }
+        }
+    }
+
+    /// Whether moving the highlight only has to redraw the two lines it moves between.
+    fn draws_incrementally(&self) -> bool {
+        self.preview.is_none() && self.rows.is_empty() && self.disabled.is_empty() && !self.grid
+    }
+
+    /// How many lines the options take when none are scrolled away.
+    fn option_lines(&self) -> usize {
+        self.options.len().div_ceil(self.grid_columns)
+    }
+
+    /// The line of the option at `index`, counted from the first option.
+    fn line_of(&self, index: usize) -> usize {
+        index % self.option_lines().max(1)
+    }
+
+    /// How many columns the selector takes, which is also how far unhighlighted options are
+    /// indented.
+    fn selector_width(&self) -> usize {
+        self.selector.width()
+    }
+
+    /// Why the highlighted option is disabled, if it is and a reason was given.
+    fn disabled_reason(&self) -> Option<String> {
+        self.disabled.get(&self.selected_index).cloned().flatten()
+    }
+
+    /// Prints a separator as wide as the widest option.
+    fn draw_separator(&mut self) -> Result<(), Box<dyn StdError>> {
+        let width = self.selector_width()
+            + self
+                .options
+                .iter()
+                .map(|option| option.chars().count())
+                .max()
+                .unwrap_or(0);
+        queue!(
+            self.writer,
+            SetForegroundColor(self.separator_foreground_color),
+            Print("─".repeat(width)),
+            ResetColor,
+        )?;
+        Ok(())
+    }
+
+    /// The indices of the options in the group of the header at `index`.
+    fn group_members(&self, index: usize) -> Vec<usize> {
+        (index + 1..self.options.len())
+            .take_while(|member| !self.rows.contains_key(member))
+            .collect()
+    }
+
+    /// Whether every option in the group of the header at `index` is chosen.
+    fn is_group_chosen(&self, index: usize) -> bool {
+        let mut members = self.group_members(index);
+        members.retain(|member| !self.disabled.contains_key(member));
+        !members.is_empty()
+            && members
+                .iter()
+                .all(|member| self.selected_options.contains(member))
+    }
+
+    /// Prints the status line, if any, and `footer_lines`, cut to `columns`.
+    fn draw_status_and_footer(
+        &mut self,
+        footer_lines: &[String],
+        columns: u16,
+    ) -> Result<(), Box<dyn StdError>> {
+        let status = self
+            .status
+            .iter()
+            .map(|status| (status, self.status_foreground_color));
+        let footer = footer_lines
+            .iter()
+            .map(|line| (line, self.footer_foreground_color));
+        for (line, color) in status.chain(footer) {
+            let line: String = line.chars().take(columns as usize).collect();
+            queue!(
+                self.writer,
+                Clear(ClearType::CurrentLine),
+                SetForegroundColor(color),
+                Print(line),
+                ResetColor,
+                Print("\r\n"),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Starts the frame of a component drawn on a single content line, like `Confirm`: prints
+    /// `title_lines` and leaves the cursor at the start of the cleared content line.
+    fn start_line_frame<S: AsRef<str>>(
+        &mut self,
+        title_lines: &[S],
+    ) -> Result<(), Box<dyn StdError>> {
+        for line in title_lines {
+            queue!(
+                self.writer,
+                Clear(ClearType::CurrentLine),
+                Print(line.as_ref()),
+                Print("\r\n")
+            )?;
+        }
+        queue!(self.writer, Clear(ClearType::CurrentLine))?;
+        Ok(())
+    }
+
+    /// Ends the content line started by `start_line_frame`, draws the preview, status and
+    /// footer below it and moves back to the content line, recording the frame for `redraw`.
+    /// The caller flushes the writer.
+    fn finish_line_frame(
+        &mut self,
+        title_lines: usize,
+        footer_lines: &[String],
+        columns: u16,
+    ) -> Result<(), Box<dyn StdError>> {
+        queue!(self.writer, Print("\r\n"))?;
+        let preview_lines = self.draw_preview()?;
+        self.draw_status_and_footer(footer_lines, columns)?;
+        let below_lines = preview_lines + self.status.is_some() as usize + footer_lines.len();
+        queue!(
+            self.writer,
+            Clear(ClearType::FromCursorDown),
+            cursor::MoveToPreviousLine(below_lines as u16 + 1)
+        )?;
+        self.drawn_lines = title_lines + 1 + below_lines;
+        self.cursor_line = title_lines;
+        self.shown_seconds = self.seconds_left();
+        Ok(())
+    }
+
+    fn call_highlight_hook(&mut self) {
+        if let (Some(hook), Some(option)) = (
+            &mut self.on_highlight,
+            self.options.get(self.selected_index),
+        ) {
+            hook(self.selected_index, option);
+        }
+    }
+
+    fn call_toggle_hook(&mut self, index: usize) {
+        if let (Some(hook), Some(option)) = (&mut self.on_toggle, self.options.get(index)) {
+            hook(index, option, self.selected_options.contains(&index));
+        }
+    }
+
+    /// Refuses disabled options, then asks the submit hook whether `selected` may be submitted.
+    fn check_submit(&mut self, selected: HashSet<usize>) -> Result<HashSet<usize>, String> {
+        if let Some(index) = selected
+            .iter()
+            .find(|index| self.disabled.contains_key(index))
+        {
+            return Err(match &self.disabled[index] {
+                Some(reason) => reason.clone(),
+                None => format!("{} is disabled", self.options[*index]),
+            });
+        }
+        match &mut self.on_submit {
+            Some(hook) => hook(&selected).map(|_| selected),
+            None => Ok(selected),
+        }
+    }
+
+    /// Exits the process with code 1 when `outcome` is an interrupt, unless
+    /// `exit_on_interrupt` was disabled. Shared by `run` and the components' own run methods.
+    fn exit_if_interrupted(&self, outcome: &Outcome) {
+        if outcome.status == Status::Interrupted && self.exit_on_interrupt {
+            process::exit(1);
+        }
+    }
+
+    /// Starts the timeout's countdown over, if there is a timeout.
+    fn restart_timeout(&mut self) {
+        self.deadline = self.timeout.map(|(timeout, _)| Instant::now() + timeout);
+    }
+
+    /// Whole seconds until the timeout runs out, rounded up.
+    fn seconds_left(&self) -> Option<u64> {
+        let left = self.deadline?.saturating_duration_since(Instant::now());
+        Some(left.as_secs() + (left.subsec_nanos() > 0) as u64)
+    }
+
+    /// When the countdown next shows a different number of seconds.
+    fn next_tick(&self) -> Option<Instant> {
+        let deadline = self.deadline?;
+        let seconds = self.seconds_left()?.saturating_sub(1) as u32;
+        Some(deadline - SECOND * seconds)
+    }
+
+    /// How long to wait for a key before `on_tick` has to run again, `None` to wait for ever.
+    fn tick_interval(&self) -> Option<Duration> {
+        let tick = self
+            .next_tick()
+            .map(|tick| tick.saturating_duration_since(Instant::now()));
+        match (&self.option_receiver, tick) {
+            (Some(_), Some(tick)) => Some(tick.min(STREAM_POLL_INTERVAL)),
+            (Some(_), None) => Some(STREAM_POLL_INTERVAL),
+            (None, tick) => tick,
+        }
+    }
+
+    fn sort_table(&mut self, column: usize) {
+        let table = match &mut self.table {
+            Some(table) if column < table.get_columns().len() => table,
+            _ => return,
+        };
+        let order = table.sort(column);
+        let mut moved_to = vec![0; order.len()];
+        for (index, previous) in order.iter().enumerate() {
+            moved_to[*previous] = index;
+        }
+        if let Some(index) = moved_to.get(self.selected_index) {
+            self.selected_index = *index;
+        }
+        self.selected_options = self
+            .selected_options
+            .iter()
+            .filter_map(|index| moved_to.get(*index).copied())
+            .collect();
+        self.disabled = self
+            .disabled
+            .drain()
+            .filter_map(|(index, reason)| Some((*moved_to.get(index)?, reason)))
+            .collect();
+        self.options = table.format_rows();
+    }
+
+    /// Swaps in new options, keeping the highlighted and chosen options whose labels are still
+    /// there. The highlight stays at the same position when its label is gone.
+    fn replace_options(&mut self, options: Vec<String>) {
+        let highlighted = self.options.get(self.selected_index);
+        let selected_index = highlighted
+            .and_then(|label| options.iter().position(|option| option == label))
+            .unwrap_or(self.selected_index);
+        self.selected_options = options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| {
+                self.selected_options
+                    .iter()
+                    .any(|selected| self.options.get(*selected) == Some(*option))
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.selected_index = selected_index.min(options.len().saturating_sub(1));
+        self.options = options;
+        self.rows.clear();
+        self.disabled.clear();
+        self.table = None;
+    }
+
+    /// Takes out the submenu of the option chosen in `outcome`, if any, clearing this menu to
+    /// make room for it.
+    fn open_submenu(
+        &mut self,
+        outcome: &Outcome,
+    ) -> Result<Option<OpenedSubmenu>, Box<dyn StdError>> {
+        let index = match (&outcome.status, &outcome.selected) {
+            (Status::Submitted, Some(selected)) if selected.len() == 1 => {
+                *selected.iter().next().unwrap()
+            }
+            _ => return Ok(None),
+        };
+        let mut submenu = match self.submenus.remove(&index) {
+            Some(submenu) => submenu,
+            None => return Ok(None),
+        };
+        self.erase_frame()?;
+
+        let mut breadcrumb = self.breadcrumb.clone();
+        if !self.title.is_empty() {
+            breadcrumb.push(self.title.clone());
+        }
+        let mut_submenu = submenu.get_menu_mut();
+        mut_submenu.breadcrumb = breadcrumb;
+        if mut_submenu.title.is_empty() {
+            mut_submenu.title = self.options[index].clone();
+        }
+        std::mem::swap(&mut self.writer, &mut mut_submenu.writer);
+        Ok(Some((index, submenu)))
+    }
+
+    /// Puts a submenu taken by `open_submenu` back. Returns the outcome to end with, or `None`
+    /// when the submenu was left to come back to this menu.
+    fn close_submenu(
+        &mut self,
+        index: usize,
+        mut submenu: Box<dyn MenuLike>,
+        submenu_outcome: Result<Outcome, Box<dyn StdError>>,
+    ) -> Result<Option<Outcome>, Box<dyn StdError>> {
+        let mut_submenu = submenu.get_menu_mut();
+        let erased = match &submenu_outcome {
+            Ok(outcome) if outcome.status == Status::Cancelled => mut_submenu.erase_frame(),
+            _ => Ok(()),
+        };
+        std::mem::swap(&mut self.writer, &mut mut_submenu.writer);
+        self.submenus.insert(index, submenu);
+        erased?;
+
+        let mut submenu_outcome = submenu_outcome?;
+        if submenu_outcome.status == Status::Cancelled {
+            return Ok(None);
+        }
+        submenu_outcome.path.insert(0, index);
+        Ok(Some(submenu_outcome))
+    }
+
+    /// Clears what the menu drew, leaving the cursor where its first line was. Expects the
+    /// console to be restored already. Does nothing after the line based fallback, which draws
+    /// no frame.
+    fn erase_frame(&mut self) -> Result<(), Box<dyn StdError>> {
+        if self.fullscreen || self.drawn_lines == 0 {
+            return Ok(());
+        }
+        execute!(
+            self.writer,
+            cursor::MoveToPreviousLine(self.drawn_lines as u16),
+            Clear(ClearType::FromCursorDown)
+        )?;
+        self.drawn_lines = 0;
+        self.cursor_line = 0;
+        Ok(())
+    }
+
+    fn preview_lines_len(&self) -> usize {
+        match self.preview {
+            Some(_) => self.preview_height + 2,
+            None => 0,
+        }
+    }
+
+    /// How many options fit next to `fixed_lines` other lines on a terminal with `rows` rows.
+    fn option_rows_for(&self, rows: u16, fixed_lines: usize) -> usize {
+        // the line below the menu is kept free so the last newline never scrolls it away
+        let max_lines = (rows as usize).saturating_sub(1);
+        let max_lines = match self.height {
+            Some(height) if !self.fullscreen => height.lines(rows).min(max_lines),
+            _ => max_lines,
+        };
+        max_lines
+            .saturating_sub(fixed_lines)
+            .clamp(1, self.option_lines().max(1))
+    }
+
+    fn is_visible(&self, index: usize) -> bool {
+        index >= self.scroll_offset && index < self.scroll_offset + self.option_rows
+    }
+
+    /// Moves the visible window of `option_rows` lines of options so that `line` is inside it.
+    fn scroll_to(&mut self, line: usize, option_rows: usize) {
+        if line < self.scroll_offset {
+            self.scroll_offset = line;
+        } else if line >= self.scroll_offset + option_rows {
+            self.scroll_offset = line + 1 - option_rows;
+        }
+        self.scroll_offset = self
+            .scroll_offset
+            .min(self.option_lines().saturating_sub(option_rows));
+        self.option_rows = option_rows;
+    }
+}
+
 impl Default for Menu {
     fn default() -> Self {
         Self {
-            stdout: stdout(),
+            writer: Box::new(stdout()),
             title: String::from("Single Select Menu"),
             options: vec![
                 String::from("Option 1"),
                 String::from("Option 2"),
                 String::from("Option 3"),
             ],
+            rows: HashMap::new(),
+            disabled: HashMap::new(),
+            table: None,
             selected_options: HashSet::new(),
             selected_index: 0,
             new_line_count: 0,
             selector: String::from("=> "),
             selected_foreground_color: Color::Reset,
             selected_background_color: Color::Reset,
+            preview: None,
+            preview_height: 10,
+            preview_lines: Vec::new(),
+            preview_scroll: 0,
+            reload: None,
+            key_bindings: keymap::default_key_bindings(),
+            footer: false,
+            footer_foreground_color: Color::DarkGrey,
+            header_foreground_color: Color::Reset,
+            separator_foreground_color: Color::DarkGrey,
+            status: None,
+            status_foreground_color: Color::Yellow,
+            on_highlight: None,
+            on_toggle: None,
+            on_submit: None,
+            show_help: false,
+            submenus: HashMap::new(),
+            breadcrumb: Vec::new(),
+            option_receiver: None,
+            timeout: None,
+            deadline: None,
+            shown_seconds: None,
+            exit_on_interrupt: true,
+            fullscreen: false,
+            grid: false,
+            grid_columns: 1,
+            cell_width: 0,
+            height: None,
+            scroll_offset: 0,
+            option_rows: 0,
+            drawn_lines: 0,
+            cursor_line: 0,
         }
     }
 }
@@ -340,6 +2119,7 @@ pub struct MultiMenu {
 }
 
 impl MultiMenu {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         title: String,
         options: Vec<String>,
@@ -375,6 +2155,10 @@ impl Default for MultiMenu {
     fn default() -> Self {
         let mut menu = Menu::default();
         menu.title(String::from("Multi Select Menu"));
+        menu.bind(
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+            Action::Toggle,
+        );
         Self {
             menu,
             selected_selector: String::from("-> "),
@@ -414,16 +2198,16 @@ impl MenuLike for MultiMenu {
         let selector = &mut_menu.selector;
         let selected_foreground_color = mut_menu.selected_foreground_color;
         let selected_background_color = mut_menu.selected_background_color;
-        let dist = mut_menu.selector.len() as u16;
+        let dist = mut_menu.selector_width() as u16;
 
-        queue!(mut_menu.stdout, Clear(ClearType::CurrentLine))?;
+        queue!(mut_menu.writer, Clear(ClearType::CurrentLine))?;
         let cond = match direction {
-            Direction::Up => (mut_menu.selected_index + 1),
-            Direction::Down => (mut_menu.selected_index - 1),
+            Direction::Up => mut_menu.selected_index + 1,
+            Direction::Down => mut_menu.selected_index - 1,
         };
         if mut_menu.selected_options.contains(&cond) {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 SetForegroundColor(selected_option_foreground_color),
                 SetBackgroundColor(selected_option_background_color),
                 Print(selected_selector),
@@ -432,7 +2216,7 @@ impl MenuLike for MultiMenu {
             )?;
         } else {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 cursor::MoveRight(dist),
                 Print(current_line_out),
             )?;
@@ -440,14 +2224,14 @@ impl MenuLike for MultiMenu {
         match direction {
             Direction::Up => {
                 queue!(
-                    mut_menu.stdout,
+                    mut_menu.writer,
                     cursor::MoveToPreviousLine(1),
                     Clear(ClearType::CurrentLine)
                 )?;
             }
             Direction::Down => {
                 queue!(
-                    mut_menu.stdout,
+                    mut_menu.writer,
                     cursor::MoveToNextLine(1),
                     Clear(ClearType::CurrentLine)
                 )?;
@@ -455,19 +2239,19 @@ impl MenuLike for MultiMenu {
         }
         if mut_menu.selected_options.contains(&mut_menu.selected_index) {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 SetForegroundColor(selected_selected_option_foreground_color),
                 SetBackgroundColor(selected_selected_option_background_color),
             )?;
         } else {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 SetForegroundColor(selected_foreground_color),
                 SetBackgroundColor(selected_background_color),
             )?;
         }
         queue!(
-            mut_menu.stdout,
+            mut_menu.writer,
             Print(selector),
             Print(next_line_out),
             ResetColor,
@@ -477,7 +2261,33 @@ impl MenuLike for MultiMenu {
         Ok(())
     }
 
+    fn is_multi_select(&self) -> bool {
+        true
+    }
+
+    /// Headers can be highlighted too, to toggle their group.
+    fn is_selectable(&self, index: usize) -> bool {
+        self.menu.rows.get(&index) != Some(&Row::Separator)
+    }
+
     fn on_space_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        if self.menu.disabled.contains_key(&self.menu.selected_index) {
+            return Ok(());
+        }
+        if !self.menu.rows.is_empty() {
+            return self.toggle_in_groups();
+        }
+        if self.menu.options.is_empty() {
+            return Ok(());
+        }
+        if !self.menu.draws_incrementally() {
+            let index = self.menu.selected_index;
+            if !self.menu.selected_options.remove(&index) {
+                self.menu.selected_options.insert(index);
+            }
+            self.menu.call_toggle_hook(index);
+            return self.redraw();
+        }
         let selected_selected_option_foreground_color =
             self.selected_selected_option_foreground_color;
         let selected_selected_option_background_color =
@@ -487,29 +2297,30 @@ impl MenuLike for MultiMenu {
         let selected_foreground_color = mut_menu.selected_foreground_color;
         let selected_background_color = mut_menu.selected_background_color;
         let option = mut_menu.format_option(mut_menu.selected_index);
-        queue!(mut_menu.stdout, Clear(ClearType::CurrentLine))?;
+        queue!(mut_menu.writer, Clear(ClearType::CurrentLine))?;
         if mut_menu.selected_options.contains(&mut_menu.selected_index) {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 SetForegroundColor(selected_foreground_color),
                 SetBackgroundColor(selected_background_color),
             )?;
             mut_menu.selected_options.remove(&mut_menu.selected_index);
         } else {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
                 SetForegroundColor(selected_selected_option_foreground_color),
                 SetBackgroundColor(selected_selected_option_background_color),
             )?;
             mut_menu.selected_options.insert(mut_menu.selected_index);
         }
         queue!(
-            mut_menu.stdout,
+            mut_menu.writer,
             Print(selector),
             Print(option),
             ResetColor,
             cursor::MoveToColumn(1)
         )?;
+        mut_menu.call_toggle_hook(mut_menu.selected_index);
         Ok(())
     }
 
@@ -520,7 +2331,7 @@ impl MenuLike for MultiMenu {
         Ok(Some(self.get_selected_options().clone()))
     }
 
-    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
+    fn draw_option(&mut self, index: usize) -> Result<(), Box<dyn StdError>> {
         let selected_selector = &self.selected_selector.clone();
         let selected_option_foreground_color = self.selected_option_foreground_color;
         let selected_option_background_color = self.selected_option_background_color;
@@ -529,67 +2340,91 @@ impl MenuLike for MultiMenu {
         let selected_selected_option_background_color =
             self.selected_selected_option_background_color;
 
+        let option = self.format_option(index);
         let mut_menu = self.get_menu_mut();
-
-        let title = mut_menu.format_title();
-        print!("{}", title);
-        for i in 0..mut_menu.options.len() {
-            let option = mut_menu.format_option(i);
-            if i == mut_menu.selected_index {
-                let selector = &mut_menu.selector;
-                let selected_foreground_color = mut_menu.selected_foreground_color;
-                let selected_background_color = mut_menu.selected_background_color;
-                if mut_menu.selected_options.contains(&i) {
-                    queue!(
-                        mut_menu.stdout,
-                        SetForegroundColor(selected_selected_option_foreground_color),
-                        SetBackgroundColor(selected_selected_option_background_color),
-                        Print(selector),
-                        Print(option),
-                        ResetColor,
-                        Print("\n")
-                    )?;
-                    continue;
-                }
+        let selector = &mut_menu.selector;
+        let chosen = match mut_menu.rows.get(&index) {
+            Some(Row::Separator) => return mut_menu.draw_separator(),
+            Some(Row::Header) => mut_menu.is_group_chosen(index),
+            None => mut_menu.selected_options.contains(&index),
+        };
+        if mut_menu.disabled.contains_key(&index) {
+            queue!(mut_menu.writer, SetAttribute(Attribute::Dim))?;
+        }
+        if index == mut_menu.selected_index {
+            if chosen {
                 queue!(
-                    mut_menu.stdout,
-                    SetForegroundColor(selected_foreground_color),
-                    SetBackgroundColor(selected_background_color),
-                    Print(selector),
-                    Print(option),
-                    ResetColor,
-                    Print("\n")
+                    mut_menu.writer,
+                    SetForegroundColor(selected_selected_option_foreground_color),
+                    SetBackgroundColor(selected_selected_option_background_color),
                 )?;
-                continue;
-            }
-            if mut_menu.selected_options.contains(&i) {
+            } else {
                 queue!(
-                    mut_menu.stdout,
-                    SetForegroundColor(selected_option_foreground_color),
-                    SetBackgroundColor(selected_option_background_color),
-                    Print(selected_selector),
-                    Print(option),
-                    ResetColor,
-                    Print("\n")
+                    mut_menu.writer,
+                    SetForegroundColor(mut_menu.selected_foreground_color),
+                    SetBackgroundColor(mut_menu.selected_background_color),
                 )?;
-                continue;
             }
-            let dist = mut_menu.selector.len() as u16;
+            queue!(mut_menu.writer, Print(selector), Print(option), ResetColor)?;
+        } else if chosen {
             queue!(
-                mut_menu.stdout,
+                mut_menu.writer,
+                SetForegroundColor(selected_option_foreground_color),
+                SetBackgroundColor(selected_option_background_color),
+                Print(selected_selector),
+                Print(option),
+                ResetColor,
+            )?;
+        } else if mut_menu.rows.contains_key(&index) {
+            let dist = mut_menu.selector_width() as u16;
+            queue!(
+                mut_menu.writer,
                 cursor::MoveRight(dist),
+                SetForegroundColor(mut_menu.header_foreground_color),
+                SetAttribute(Attribute::Bold),
                 Print(option),
-                Print("\n"),
+                ResetColor,
             )?;
+        } else {
+            let dist = mut_menu.selector_width() as u16;
+            queue!(mut_menu.writer, cursor::MoveRight(dist), Print(option))?;
         }
-        let dist = (mut_menu.options.len() - mut_menu.selected_index) as u16;
-        queue!(mut_menu.stdout, cursor::MoveToPreviousLine(dist))?;
-        mut_menu.stdout.flush()?;
+        queue!(mut_menu.writer, SetAttribute(Attribute::Reset))?;
         Ok(())
     }
 }
 
 impl MultiMenu {
+    /// Toggles the highlighted option, or every option of the highlighted header's group, and
+    /// draws the menu again since a header shows whether its whole group is chosen.
+    fn toggle_in_groups(&mut self) -> Result<(), Box<dyn StdError>> {
+        let menu = &mut self.menu;
+        let index = menu.selected_index;
+        let toggled = match menu.rows.get(&index) {
+            Some(Row::Header) => menu
+                .group_members(index)
+                .into_iter()
+                .filter(|member| !menu.disabled.contains_key(member))
+                .collect(),
+            Some(Row::Separator) => return Ok(()),
+            None if index < menu.options.len() => vec![index],
+            None => return Ok(()),
+        };
+        let choose = !toggled
+            .iter()
+            .all(|member| menu.selected_options.contains(member));
+        for member in toggled {
+            let changed = match choose {
+                true => menu.selected_options.insert(member),
+                false => menu.selected_options.remove(&member),
+            };
+            if changed {
+                menu.call_toggle_hook(member);
+            }
+        }
+        self.redraw()
+    }
+
     //TODO selector and selected_selector must be the same length
     pub fn selected_selector(&mut self, selected_selector: String) {
         self.selected_selector = selected_selector;
@@ -611,3 +2446,43 @@ impl MultiMenu {
         self.selected_selected_option_background_color = color;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(options: &[&str]) -> Menu {
+        let mut menu = Menu::default();
+        menu.options(options.iter().map(|option| option.to_string()).collect());
+        menu
+    }
+
+    #[test]
+    fn removing_an_option_shifts_the_ones_after_it() {
+        let mut menu = menu(&["Services", "api", "web", "db", "cache"]);
+        menu.rows.insert(0, Row::Header);
+        menu.disabled.insert(1, None);
+        menu.disabled.insert(3, Some(String::from("down")));
+        menu.selected_options = HashSet::from([1, 2, 4]);
+        menu.selected_index = 3;
+        menu.apply_option_update(OptionUpdate::Remove(2));
+        assert_eq!(menu.options, vec!["Services", "api", "db", "cache"]);
+        assert_eq!(menu.rows, HashMap::from([(0, Row::Header)]));
+        assert_eq!(
+            menu.disabled,
+            HashMap::from([(1, None), (2, Some(String::from("down")))])
+        );
+        assert_eq!(menu.selected_options, HashSet::from([1, 3]));
+        assert_eq!(menu.selected_index, 2);
+    }
+
+    #[test]
+    fn removing_the_last_option_moves_the_highlight_up() {
+        let mut menu = menu(&["api", "web"]);
+        menu.selected_index = 1;
+        menu.apply_option_update(OptionUpdate::Remove(1));
+        assert_eq!(menu.selected_index, 0);
+        menu.apply_option_update(OptionUpdate::Remove(5));
+        assert_eq!(menu.options, vec!["api"]);
+    }
+}
@@ -1,36 +1,477 @@
+mod launcher;
+
+use launcher::{Entry, Launch};
 use menu::Menu;
 use menu::MenuLike;
 use menu::MultiMenu;
+use menu::{Outcome, Status, TimeoutAnswer};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::{self, Command};
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: menu [OPTIONS] [OPTION...]
+       menu launch CONFIG
+
+Shows a menu of the given options, or of the lines read from stdin when none
+are given (or printed by --reload-cmd, when given), and prints the chosen
+option(s) to stdout.
+
+Exit status is 0 when options were submitted, 1 when the menu was cancelled
+with esc, 130 when it was interrupted with ctrl-c and 2 on usage or terminal
+errors.
+
+`menu launch` reads a tree of menus from a TOML or YAML file, lets you walk
+down to a command (esc goes back up a level) and runs it, exiting with the
+command's exit status.
+
+Options:
+  -m, --multi            Allow choosing several options with space
+  -t, --title TITLE      Title shown above the options
+  -s, --selected LABEL   Option that starts out chosen (repeatable, --multi only)
+  -i, --index INDEX      Index of the option highlighted first (0-based)
+  -p, --preview COMMAND  Shell command whose output is previewed for the
+                         highlighted option, {} is replaced by the option
+  -r, --reload-cmd COMMAND
+                         Shell command whose output lines replace the options
+                         when ctrl-r is pressed
+  -T, --timeout SECONDS  Answer on its own when no key is pressed for this long
+      --timeout-answer ANSWER
+                         What to answer on timeout: highlighted (default),
+                         cancel or the index of an option
+  -g, --grid             Lay the options out in as many columns as fit
+  -o, --output FORMAT    How the chosen options are printed: lines (default),
+                         nul, index or json
+  -h, --help             Print this help";
+
+#[derive(Default, Clone, Copy)]
+enum Output {
+    #[default]
+    Lines,
+    Nul,
+    Index,
+    Json,
+}
+
+#[derive(Default)]
+struct Args {
+    multi: bool,
+    title: String,
+    selected: Vec<String>,
+    index: usize,
+    preview: Option<String>,
+    reload: Option<String>,
+    timeout: Option<u64>,
+    timeout_answer: Option<TimeoutAnswer>,
+    grid: bool,
+    output: Output,
+    options: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(format!("{} needs a value", name))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-m" | "--multi" => parsed.multi = true,
+            "-t" | "--title" => parsed.title = value("--title")?,
+            "-s" | "--selected" => parsed.selected.push(value("--selected")?),
+            "-i" | "--index" => {
+                let index = value("--index")?;
+                parsed.index = index
+                    .parse()
+                    .map_err(|_| format!("--index expects a number, got {}", index))?;
+            }
+            "-p" | "--preview" => parsed.preview = Some(value("--preview")?),
+            "-r" | "--reload-cmd" => parsed.reload = Some(value("--reload-cmd")?),
+            "-T" | "--timeout" => {
+                let timeout = value("--timeout")?;
+                parsed.timeout = Some(
+                    timeout
+                        .parse()
+                        .map_err(|_| format!("--timeout expects seconds, got {}", timeout))?,
+                );
+            }
+            "--timeout-answer" => {
+                parsed.timeout_answer = match value("--timeout-answer")?.as_str() {
+                    "highlighted" => Some(TimeoutAnswer::Highlighted),
+                    "cancel" => Some(TimeoutAnswer::Cancel),
+                    answer => match answer.parse() {
+                        Ok(index) => Some(TimeoutAnswer::Index(index)),
+                        Err(_) => return Err(format!("unknown timeout answer {}", answer)),
+                    },
+                }
+            }
+            "-g" | "--grid" => parsed.grid = true,
+            "-o" | "--output" => {
+                parsed.output = match value("--output")?.as_str() {
+                    "lines" => Output::Lines,
+                    "nul" => Output::Nul,
+                    "index" => Output::Index,
+                    "json" => Output::Json,
+                    output => return Err(format!("unknown output format {}", output)),
+                }
+            }
+            "--" => {
+                parsed.options.extend(args.by_ref());
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option {}", flag));
+            }
+            _ => parsed.options.push(arg),
+        }
+    }
+    Ok(parsed)
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn preview_command(command: String) -> menu::Preview {
+    Box::new(move |option| {
+        let command = command.replace("{}", &shell_quote(option));
+        match Command::new("sh").arg("-c").arg(&command).output() {
+            Ok(output) => {
+                let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                text.push_str(&String::from_utf8_lossy(&output.stderr));
+                text
+            }
+            Err(error) => format!("{}: {}", command, error),
+        }
+    })
+}
+
+/// Runs `command` on reload, keeping the current options when it cannot be run or fails.
+fn reload_command(command: String) -> menu::Reload {
+    Box::new(move || {
+        let output = Command::new("sh").arg("-c").arg(&command).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let options = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        Some(options)
+    })
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn print_outcome(
+    stdout: &mut impl Write,
+    outcome: &Outcome,
+    options: &[String],
+    output: Output,
+) -> io::Result<()> {
+    let mut indices: Vec<usize> = outcome.selected.iter().flatten().copied().collect();
+    indices.sort_unstable();
+    match output {
+        Output::Lines => {
+            for index in indices {
+                writeln!(stdout, "{}", options[index])?;
+            }
+        }
+        Output::Nul => {
+            for index in indices {
+                write!(stdout, "{}\0", options[index])?;
+            }
+        }
+        Output::Index => {
+            for index in indices {
+                writeln!(stdout, "{}", index)?;
+            }
+        }
+        Output::Json => {
+            let selected: Vec<String> = indices
+                .iter()
+                .map(|index| {
+                    format!(
+                        "{{\"index\":{},\"label\":{}}}",
+                        index,
+                        json_string(&options[*index])
+                    )
+                })
+                .collect();
+            let key = match &outcome.key {
+                Some(key) => json_string(&menu::key_label(key)),
+                None => String::from("null"),
+            };
+            writeln!(
+                stdout,
+                "{{\"cancelled\":{},\"key\":{},\"selected\":[{}]}}",
+                outcome.status == Status::Cancelled,
+                key,
+                selected.join(",")
+            )?;
+        }
+    }
+    stdout.flush()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("menu: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+/// A menu drawn on the controlling terminal, so that stdout only carries results and the menu
+/// works inside pipelines.
+fn terminal_menu(multi: bool) -> Box<dyn MenuLike> {
+    let mut menu: Box<dyn MenuLike> = match multi {
+        true => Box::new(MultiMenu::default()),
+        false => Box::new(Menu::default()),
+    };
+    if menu.tty().is_err() {
+        menu.writer(Box::new(io::stderr()));
+    }
+    menu
+}
+
+fn launch(config: &str) -> ! {
+    let root = match Entry::load(Path::new(config)) {
+        Ok(root) => root,
+        Err(error) => {
+            eprintln!("menu: {}: {}", config, error);
+            process::exit(2);
+        }
+    };
+    match launcher::run(&root, || terminal_menu(false)) {
+        Ok(Launch::Exited(code)) => process::exit(code),
+        Ok(Launch::Cancelled) => process::exit(1),
+        Ok(Launch::Interrupted) => process::exit(130),
+        Err(error) => {
+            eprintln!("menu: {}", error);
+            process::exit(2);
+        }
+    }
+}
 
 fn main() {
-    let mut selected_options = std::collections::HashSet::new();
-    selected_options.insert(1);
-    let mut multimenu = MultiMenu::default();
-    multimenu.selected_background_color(menu::Color::Black);
-    multimenu.selected_foreground_color(menu::Color::Yellow);
-    multimenu.selected_options(selected_options);
-
-    multimenu.selected_selected_option_background_color(menu::Color::White);
-    multimenu.selected_selected_option_foreground_color(menu::Color::Red);
-
-    let mut menu = Menu::default();
-    menu.selected_background_color(menu::Color::Black);
-    menu.selected_foreground_color(menu::Color::Yellow);
-
-
-    let menus: Vec<Box<dyn MenuLike>>  = vec![Box::new(multimenu), Box::new(menu)];
-    for mut menu in menus {
-        let res = menu.run().unwrap();
-        if let Some(indecies) = res {
-            println!(
-                "You selected: {:?}",
-                indecies
-                    .iter()
-                    .map(|i| menu.get_options()[*i].clone())
-                    .collect::<Vec<String>>()
-            );
-        } else {
-            println!("You didn't select anything");
+    let mut raw_args = std::env::args().skip(1).peekable();
+    if raw_args.peek().map(String::as_str) == Some("launch") {
+        let args: Vec<String> = raw_args.skip(1).collect();
+        match args.as_slice() {
+            [config] => launch(config),
+            _ => fail("launch expects exactly one config file"),
+        }
+    }
+    let mut args = match parse_args(raw_args) {
+        Ok(args) => args,
+        Err(message) => fail(&message),
+    };
+    let reload = args.reload.take().map(reload_command);
+    if let (true, Some(reload)) = (args.options.is_empty(), &reload) {
+        args.options = reload().unwrap_or_default();
+    } else if args.options.is_empty() {
+        args.options = io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+    if args.options.is_empty() {
+        fail("no options given");
+    }
+    let selected_options: HashSet<usize> = args
+        .selected
+        .iter()
+        .filter_map(|label| args.options.iter().position(|option| option == label))
+        .collect();
+
+    let mut menu = terminal_menu(args.multi);
+    menu.title(args.title);
+    menu.options(args.options);
+    menu.selected_options(selected_options);
+    menu.selected_index(args.index);
+    if args.grid {
+        menu.grid(true);
+    }
+    if let Some(preview) = args.preview {
+        menu.preview(preview_command(preview));
+    }
+    if let Some(reload) = reload {
+        menu.reload(reload);
+    }
+    if let Some(timeout) = args.timeout {
+        let answer = args.timeout_answer.unwrap_or(TimeoutAnswer::Highlighted);
+        menu.timeout(Duration::from_secs(timeout), answer);
+    }
+
+    let outcome = match menu.run_outcome() {
+        Ok(outcome) => outcome,
+        Err(error) => {
+            eprintln!("menu: {}", error);
+            process::exit(2);
         }
+    };
+    if outcome.status == Status::Interrupted {
+        process::exit(130);
+    }
+    let printed = print_outcome(
+        &mut io::stdout().lock(),
+        &outcome,
+        menu.get_options(),
+        args.output,
+    );
+    if let Err(error) = printed {
+        eprintln!("menu: {}", error);
+        process::exit(2);
+    }
+    if outcome.status == Status::Cancelled {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use menu::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn outcome(status: Status, selected: &[usize], key: Option<KeyEvent>) -> Outcome {
+        let selected: HashSet<usize> = selected.iter().copied().collect();
+        Outcome::new(status, Some(selected).filter(|s| !s.is_empty()), key)
+    }
+
+    fn printed(outcome: &Outcome, output: Output) -> String {
+        let options = ["b\"c", "tab\there", "plain"].map(String::from);
+        let mut printed = Vec::new();
+        print_outcome(&mut printed, outcome, &options, output).unwrap();
+        String::from_utf8(printed).unwrap()
+    }
+
+    #[test]
+    fn json_string_escapes_quotes_and_control_characters() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("\n\r\t"), "\"\\n\\r\\t\"");
+        assert_eq!(json_string("\u{1}é"), "\"\\u0001é\"");
+    }
+
+    #[test]
+    fn print_outcome_prints_chosen_options_in_order() {
+        let outcome = outcome(Status::Submitted, &[2, 0], None);
+        assert_eq!(printed(&outcome, Output::Lines), "b\"c\nplain\n");
+        assert_eq!(printed(&outcome, Output::Nul), "b\"c\0plain\0");
+        assert_eq!(printed(&outcome, Output::Index), "0\n2\n");
+    }
+
+    #[test]
+    fn print_outcome_prints_json() {
+        let key = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        let outcome = outcome(Status::Submitted, &[1], Some(key));
+        assert_eq!(
+            printed(&outcome, Output::Json),
+            format!(
+                "{{\"cancelled\":false,\"key\":{},\"selected\":[{{\"index\":1,\"label\":\"tab\\there\"}}]}}\n",
+                json_string(&menu::key_label(&key))
+            )
+        );
+    }
+
+    #[test]
+    fn print_outcome_prints_nothing_chosen_when_cancelled() {
+        let outcome = outcome(Status::Cancelled, &[], None);
+        assert_eq!(printed(&outcome, Output::Lines), "");
+        assert_eq!(
+            printed(&outcome, Output::Json),
+            "{\"cancelled\":true,\"key\":null,\"selected\":[]}\n"
+        );
+    }
+
+    #[test]
+    fn parse_args_reads_flags_and_options() {
+        let args = parse(&["-m", "--title", "Pick", "-s", "b", "-i", "1", "a", "b"]).unwrap();
+        assert!(args.multi);
+        assert_eq!(args.title, "Pick");
+        assert_eq!(args.selected, vec!["b"]);
+        assert_eq!(args.index, 1);
+        assert_eq!(args.options, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn parse_args_reads_inline_values() {
+        let args = parse(&["--title=a=b", "--timeout=5", "--timeout-answer=2", "x"]).unwrap();
+        assert_eq!(args.title, "a=b");
+        assert_eq!(args.timeout, Some(5));
+        assert_eq!(args.timeout_answer, Some(TimeoutAnswer::Index(2)));
+        assert_eq!(args.options, vec!["x"]);
+    }
+
+    #[test]
+    fn parse_args_passes_everything_after_double_dash() {
+        let args = parse(&["a", "--", "-m", "--title", "--"]).unwrap();
+        assert!(!args.multi);
+        assert_eq!(args.options, vec!["a", "-m", "--title", "--"]);
+    }
+
+    #[test]
+    fn parse_args_takes_a_dash_as_an_option() {
+        let args = parse(&["-", "a"]).unwrap();
+        assert_eq!(args.options, vec!["-", "a"]);
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_flags() {
+        assert_eq!(parse(&["--bogus"]).err().unwrap(), "unknown option --bogus");
+        assert_eq!(parse(&["-x", "a"]).err().unwrap(), "unknown option -x");
+    }
+
+    #[test]
+    fn parse_args_rejects_missing_and_bad_values() {
+        assert_eq!(
+            parse(&["a", "--title"]).err().unwrap(),
+            "--title needs a value"
+        );
+        assert_eq!(
+            parse(&["-i", "x"]).err().unwrap(),
+            "--index expects a number, got x"
+        );
+        assert_eq!(
+            parse(&["-o", "xml"]).err().unwrap(),
+            "unknown output format xml"
+        );
+        assert_eq!(
+            parse(&["--timeout-answer", "later"]).err().unwrap(),
+            "unknown timeout answer later"
+        );
     }
 }
@@ -0,0 +1,157 @@
+use crate::{Action, KeyCode, KeyEvent, KeyModifiers, Menu, MenuLike};
+use crossterm::{
+    style::{Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+use std::error::Error as StdError;
+
+const LEFT_ARROW: &str = "‹ ";
+const RIGHT_ARROW: &str = " ›";
+
+/// The options on a single line below the title, like a tab bar. Left and right move between
+/// them, and the highlighted one is drawn in reverse instead of with a selector. When they do
+/// not fit the terminal the bar scrolls, with arrows showing that there are more.
+pub struct MenuBar {
+    menu: Menu,
+    /// The first option shown.
+    offset: usize,
+}
+
+impl MenuBar {
+    /// An empty `title` leaves only the bar.
+    pub fn new(title: String, options: Vec<String>) -> MenuBar {
+        let mut bar = MenuBar {
+            menu: Menu::default(),
+            offset: 0,
+        };
+        bar.title(title);
+        bar.options(options);
+        bar.bind(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE), Action::Up);
+        bar.bind(
+            KeyEvent::new(KeyCode::Right, KeyModifiers::NONE),
+            Action::Down,
+        );
+        bar
+    }
+
+    /// The label of the option at `index` as drawn, cut short to fit `columns`.
+    fn cell(&self, index: usize, columns: usize) -> String {
+        let option = self.format_option(index);
+        let arrows = LEFT_ARROW.chars().count() + RIGHT_ARROW.chars().count();
+        let width = columns.saturating_sub(arrows + 2);
+        match option.chars().count() > width {
+            true => format!(
+                " {}… ",
+                option
+                    .chars()
+                    .take(width.saturating_sub(1))
+                    .collect::<String>()
+            ),
+            false => format!(" {} ", option),
+        }
+    }
+
+    /// Whether the options from `start` up to `end` fit `columns`, with their arrows.
+    fn fits(&self, cells: &[String], start: usize, end: usize, columns: usize) -> bool {
+        let arrows = (start > 0) as usize + (end < cells.len()) as usize;
+        let width: usize = cells[start..end]
+            .iter()
+            .map(|cell| cell.chars().count())
+            .sum();
+        width + end.saturating_sub(start + 1) + arrows * 2 <= columns
+    }
+
+    /// Scrolls the bar so that the highlighted option is shown and returns the options shown.
+    fn scroll(&mut self, cells: &[String], columns: usize) -> (usize, usize) {
+        let selected = self.menu.selected_index.min(cells.len().saturating_sub(1));
+        self.offset = self.offset.min(selected);
+        while self.offset < selected && !self.fits(cells, self.offset, selected + 1, columns) {
+            self.offset += 1;
+        }
+        let mut end = (selected + 1).min(cells.len());
+        while end < cells.len() && self.fits(cells, self.offset, end + 1, columns) {
+            end += 1;
+        }
+        (self.offset, end)
+    }
+}
+
+impl MenuLike for MenuBar {
+    fn get_menu_mut(&mut self) -> &mut Menu {
+        &mut self.menu
+    }
+
+    fn get_menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    /// The bar is a single line however many options there are.
+    fn option_lines_for(&self, _rows: u16, _fixed_lines: usize) -> usize {
+        1
+    }
+
+    fn on_up_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.menu.selected_index;
+        if let Some(previous) = (0..index).rev().find(|index| self.is_selectable(*index)) {
+            self.menu.selected_index = previous;
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_down_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.menu.selected_index;
+        let len = self.menu.options.len();
+        if let Some(next) = (index + 1..len).find(|index| self.is_selectable(*index)) {
+            self.menu.selected_index = next;
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Draws the title, then the bar on one line.
+    fn display(&mut self) -> Result<(), Box<dyn StdError>> {
+        let title = self.format_title();
+        let footer_lines = self.footer_lines();
+        let (columns, _) = terminal::size()?;
+        let cells: Vec<String> = (0..self.menu.options.len())
+            .map(|index| self.cell(index, columns as usize))
+            .collect();
+        let (start, end) = self.scroll(&cells, columns as usize);
+        let title_lines: Vec<&str> = title.lines().collect();
+        self.menu.start_line_frame(&title_lines)?;
+        if start > 0 {
+            queue!(self.menu.writer, Print(LEFT_ARROW))?;
+        }
+        for (index, cell) in cells.iter().enumerate().take(end).skip(start) {
+            if index > start {
+                queue!(self.menu.writer, Print(" "))?;
+            }
+            if self.menu.disabled.contains_key(&index) {
+                queue!(self.menu.writer, SetAttribute(Attribute::Dim))?;
+            }
+            if index == self.menu.selected_index {
+                queue!(
+                    self.menu.writer,
+                    SetForegroundColor(self.menu.selected_foreground_color),
+                    SetBackgroundColor(self.menu.selected_background_color),
+                    SetAttribute(Attribute::Reverse),
+                    Print(cell),
+                    ResetColor,
+                )?;
+            } else {
+                queue!(self.menu.writer, Print(cell))?;
+            }
+            queue!(self.menu.writer, SetAttribute(Attribute::Reset))?;
+        }
+        if end < cells.len() {
+            queue!(self.menu.writer, Print(RIGHT_ARROW))?;
+        }
+        self.menu
+            .finish_line_frame(title_lines.len(), &footer_lines, columns)?;
+        self.menu.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+/// A change to the options of a running menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionUpdate {
+    Push(String),
+    Update(usize, String),
+    Remove(usize),
+}
+
+/// Sends options to a running menu, see `MenuLike::option_stream`. The menu shows that it is
+/// loading until every sender is dropped.
+#[derive(Clone)]
+pub struct OptionSender {
+    /// Only `None` while dropping, so that the channel is closed before the menu is woken.
+    sender: Option<Sender<OptionUpdate>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl OptionSender {
+    pub fn push(&self, option: String) -> Result<(), SendError<OptionUpdate>> {
+        self.send(OptionUpdate::Push(option))
+    }
+
+    pub fn update(&self, index: usize, option: String) -> Result<(), SendError<OptionUpdate>> {
+        self.send(OptionUpdate::Update(index, option))
+    }
+
+    pub fn remove(&self, index: usize) -> Result<(), SendError<OptionUpdate>> {
+        self.send(OptionUpdate::Remove(index))
+    }
+
+    pub fn send(&self, update: OptionUpdate) -> Result<(), SendError<OptionUpdate>> {
+        if let Some(sender) = &self.sender {
+            sender.send(update)?;
+        }
+        self.wake();
+        Ok(())
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for OptionSender {
+    fn drop(&mut self) {
+        // the last sender going away closes the stream, which an async menu has to notice
+        drop(self.sender.take());
+        self.wake();
+    }
+}
+
+pub(crate) struct OptionReceiver {
+    receiver: Receiver<OptionUpdate>,
+    // only waited on by `run_async`
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    waker: Arc<Mutex<Option<Waker>>>,
+    pending: Option<OptionUpdate>,
+}
+
+impl OptionReceiver {
+    pub(crate) fn try_recv(&mut self) -> Result<OptionUpdate, TryRecvError> {
+        match self.pending.take() {
+            Some(update) => Ok(update),
+            None => self.receiver.try_recv(),
+        }
+    }
+
+    /// Ready once an update can be received or the stream is closed.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.pending.is_some() {
+            return Poll::Ready(());
+        }
+        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        match self.receiver.try_recv() {
+            Ok(update) => {
+                self.pending = Some(update);
+                Poll::Ready(())
+            }
+            Err(TryRecvError::Disconnected) => Poll::Ready(()),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+pub(crate) fn channel() -> (OptionSender, OptionReceiver) {
+    let (sender, receiver) = mpsc::channel();
+    let waker = Arc::new(Mutex::new(None));
+    (
+        OptionSender {
+            sender: Some(sender),
+            waker: waker.clone(),
+        },
+        OptionReceiver {
+            receiver,
+            waker,
+            pending: None,
+        },
+    )
+}
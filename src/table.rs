@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use unicode_width::UnicodeWidthStr;
+
+/// Which side of a column its cells line up on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    pub title: String,
+    pub align: Align,
+    /// Cells wider than this are cut short with `…`.
+    pub max_width: Option<usize>,
+}
+
+impl Column {
+    /// A left aligned column as wide as its widest cell.
+    pub fn new(title: String) -> Column {
+        Column {
+            title,
+            align: Align::Left,
+            max_width: None,
+        }
+    }
+}
+
+/// Options given as rows of cells, drawn in columns under a header. See `MenuLike::table`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    /// The column the rows are sorted by, and whether they are sorted descending.
+    sorted: Option<(usize, bool)>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>, rows: Vec<Vec<String>>) -> Table {
+        Table {
+            columns,
+            rows,
+            sorted: None,
+        }
+    }
+
+    pub fn get_columns(&self) -> &Vec<Column> {
+        &self.columns
+    }
+
+    /// The rows in the order they are shown, so that an option's index is also its row's.
+    pub fn get_rows(&self) -> &Vec<Vec<String>> {
+        &self.rows
+    }
+
+    pub fn get_sorted(&self) -> Option<(usize, bool)> {
+        self.sorted
+    }
+
+    /// Sorts the rows by `column`, descending when they are already sorted ascending by it,
+    /// and returns the index each row had before. Numbers are compared as numbers.
+    pub(crate) fn sort(&mut self, column: usize) -> Vec<usize> {
+        let descending = self.sorted == Some((column, false));
+        let cell = |row: usize| self.rows[row].get(column).map_or("", String::as_str);
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        order.sort_by(|a, b| {
+            let ordering = compare_cells(cell(*a), cell(*b));
+            match descending {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
+        self.rows = order.iter().map(|row| self.rows[*row].clone()).collect();
+        self.sorted = Some((column, descending));
+        order
+    }
+
+    /// The column titles, with an arrow on the sorted one.
+    fn titles(&self) -> Vec<String> {
+        (0..self.columns.len())
+            .map(|column| {
+                let title = &self.columns[column].title;
+                match self.sorted {
+                    Some((sorted, false)) if sorted == column => format!("{} ▲", title),
+                    Some((sorted, true)) if sorted == column => format!("{} ▼", title),
+                    _ => title.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn widths(&self, titles: &[String]) -> Vec<usize> {
+        (0..self.columns.len())
+            .map(|column| {
+                let width = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.width())
+                    .chain([titles[column].width()])
+                    .max()
+                    .unwrap_or(0);
+                match self.columns[column].max_width {
+                    Some(max_width) => width.min(max_width),
+                    None => width,
+                }
+            })
+            .collect()
+    }
+
+    fn format_cells(&self, cells: &[String], widths: &[usize]) -> String {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(index, (column, width))| {
+                let cell = truncate(cells.get(index).map_or("", String::as_str), *width);
+                let padding = " ".repeat(width - cell.width());
+                match column.align {
+                    Align::Left => format!("{}{}", cell, padding),
+                    Align::Right => format!("{}{}", padding, cell),
+                }
+            })
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    }
+
+    /// The header line, starting `indent` columns in to line up with the options.
+    pub(crate) fn format_header(&self, indent: usize) -> String {
+        let titles = self.titles();
+        let widths = self.widths(&titles);
+        format!(
+            "{}{}",
+            " ".repeat(indent),
+            self.format_cells(&titles, &widths)
+        )
+    }
+
+    /// The rows as option labels, padded into columns.
+    pub(crate) fn format_rows(&self) -> Vec<String> {
+        let widths = self.widths(&self.titles());
+        self.rows
+            .iter()
+            .map(|row| self.format_cells(row, &widths))
+            .collect()
+    }
+}
+
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Cuts `text` short with `…` so that it takes at most `width` columns.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    for c in text.chars() {
+        truncated.push(c);
+        if truncated.width() + 1 > width {
+            truncated.pop();
+            break;
+        }
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[&[&str]]) -> Table {
+        let mut age = Column::new(String::from("Age"));
+        age.align = Align::Right;
+        let columns = vec![Column::new(String::from("Name")), age];
+        let rows = rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect();
+        Table::new(columns, rows)
+    }
+
+    #[test]
+    fn format_rows_pads_and_aligns_columns() {
+        let table = table(&[&["api", "12"], &["worker", "3"]]);
+        assert_eq!(table.format_header(3), "   Name    Age");
+        assert_eq!(table.format_rows(), vec!["api      12", "worker    3"]);
+    }
+
+    #[test]
+    fn format_rows_counts_wide_characters_twice() {
+        let table = table(&[&["数据库", "1"], &["web", "2"]]);
+        assert_eq!(table.format_rows(), vec!["数据库    1", "web       2"]);
+    }
+
+    #[test]
+    fn format_rows_fills_missing_cells() {
+        let table = table(&[&["api"], &["web", "2"]]);
+        assert_eq!(table.format_rows(), vec!["api", "web     2"]);
+    }
+
+    #[test]
+    fn format_rows_cuts_cells_at_max_width() {
+        let mut table = table(&[&["bartholomew", "1"]]);
+        table.columns[0].max_width = Some(6);
+        assert_eq!(table.format_header(0), "Name    Age");
+        assert_eq!(table.format_rows(), vec!["barth…    1"]);
+    }
+
+    #[test]
+    fn truncate_fits_width() {
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("abcd", 3), "ab…");
+        assert_eq!(truncate("abcd", 1), "…");
+        assert_eq!(truncate("abcd", 0), "");
+    }
+
+    #[test]
+    fn truncate_does_not_split_wide_characters() {
+        assert_eq!(truncate("数据库", 4), "数…");
+        assert_eq!(truncate("数据库", 3), "数…");
+        assert_eq!(truncate("数据库", 2), "…");
+    }
+
+    #[test]
+    fn compare_cells_compares_numbers_as_numbers() {
+        assert_eq!(compare_cells("9", "10"), Ordering::Less);
+        assert_eq!(compare_cells(" 2.5", "2.5 "), Ordering::Equal);
+        assert_eq!(compare_cells("b", "A"), Ordering::Greater);
+        assert_eq!(compare_cells("10", "9a"), Ordering::Less);
+    }
+
+    #[test]
+    fn sort_toggles_direction_and_returns_previous_indices() {
+        let mut table = table(&[&["b", "10"], &["c", "9"], &["a", "100"]]);
+        assert_eq!(table.sort(1), vec![1, 0, 2]);
+        assert_eq!(table.get_sorted(), Some((1, false)));
+        assert_eq!(table.format_header(0), "Name  Age ▲");
+        assert_eq!(table.sort(1), vec![2, 1, 0]);
+        assert_eq!(table.get_sorted(), Some((1, true)));
+        assert_eq!(table.get_rows()[0][0], "a");
+        assert_eq!(table.sort(0), vec![0, 1, 2]);
+        assert_eq!(table.get_sorted(), Some((0, false)));
+        let names: Vec<&str> = table.get_rows().iter().map(|row| row[0].as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,527 @@
+use crate::{
+    run_line_fallback, Action, KeyCode, KeyEvent, KeyModifiers, Menu, MenuLike, Outcome, Status,
+};
+use std::collections::HashSet;
+use std::error::Error as StdError;
+
+/// An entry of a `TreeMenu`, with the entries below it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    pub label: String,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    pub fn new(label: String, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode { label, children }
+    }
+
+    pub fn leaf(label: String) -> TreeNode {
+        TreeNode::new(label, Vec::new())
+    }
+}
+
+/// How many of the leaves below a node are checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Check {
+    All,
+    Some,
+    None,
+}
+
+/// A `MultiMenu` over a tree. Right expands a node, left collapses it, and space checks or
+/// unchecks a node together with everything below it. Nodes with only some of their leaves
+/// checked show `[-]`.
+pub struct TreeMenu {
+    menu: Menu,
+    roots: Vec<TreeNode>,
+    /// Paths of the checked leaves, as child indices from the roots down.
+    checked: HashSet<Vec<usize>>,
+    expanded: HashSet<Vec<usize>>,
+    /// The path of each shown row, in the order of `menu.options`.
+    visible: Vec<Vec<usize>>,
+}
+
+impl TreeMenu {
+    pub fn new(title: String, roots: Vec<TreeNode>) -> TreeMenu {
+        let mut tree = TreeMenu {
+            menu: Menu::default(),
+            roots,
+            checked: HashSet::new(),
+            expanded: HashSet::new(),
+            visible: Vec::new(),
+        };
+        tree.title(title);
+        tree.bind(
+            KeyEvent::new(KeyCode::Left, KeyModifiers::NONE),
+            Action::Collapse,
+        );
+        tree.bind(
+            KeyEvent::new(KeyCode::Right, KeyModifiers::NONE),
+            Action::Expand,
+        );
+        tree.bind(
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+            Action::Toggle,
+        );
+        tree.refresh();
+        tree
+    }
+
+    /// Checks the node at `path` and everything below it. Paths that lead to no node are
+    /// ignored.
+    pub fn check(&mut self, path: Vec<usize>) {
+        if self.find(&path).is_some() {
+            self.checked.extend(self.leaves(&path));
+            self.refresh();
+        }
+    }
+
+    /// Shows the children of the node at `path`. Paths that lead to no node are ignored.
+    pub fn expand(&mut self, path: Vec<usize>) {
+        if self.find(&path).is_some() {
+            self.expanded.insert(path);
+            self.refresh();
+        }
+    }
+
+    pub fn expand_all(&mut self) {
+        let mut paths = Vec::new();
+        TreeMenu::walk(&self.roots, &mut Vec::new(), &mut |path, node| {
+            if !node.children.is_empty() {
+                paths.push(path.to_vec());
+            }
+            true
+        });
+        self.expanded.extend(paths);
+        self.refresh();
+    }
+
+    /// Runs the menu and returns the label paths of the checked leaves in tree order, or `None`
+    /// when it was cancelled. Exits the process on ctrl-c like `run`.
+    pub fn run_paths(&mut self) -> Result<Option<Vec<Vec<String>>>, Box<dyn StdError>> {
+        let outcome = self.run_outcome()?;
+        self.menu.exit_if_interrupted(&outcome);
+        match outcome.status {
+            Status::Submitted => Ok(Some(self.get_checked_labels())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Paths of the checked leaves in tree order, as child indices from the roots down.
+    pub fn get_checked_paths(&self) -> Vec<Vec<usize>> {
+        self.leaves(&[])
+            .into_iter()
+            .filter(|leaf| self.checked.contains(leaf))
+            .collect()
+    }
+
+    /// Paths of the checked leaves in tree order, as the labels from the roots down.
+    pub fn get_checked_labels(&self) -> Vec<Vec<String>> {
+        self.get_checked_paths()
+            .iter()
+            .map(|path| {
+                (1..=path.len())
+                    .map(|depth| self.node(&path[..depth]).label.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Calls `visit` with the path of every node in tree order, descending into a node's
+    /// children when it returns true.
+    fn walk(
+        nodes: &[TreeNode],
+        path: &mut Vec<usize>,
+        visit: &mut impl FnMut(&[usize], &TreeNode) -> bool,
+    ) {
+        for (index, node) in nodes.iter().enumerate() {
+            path.push(index);
+            if visit(path, node) {
+                TreeMenu::walk(&node.children, path, visit);
+            }
+            path.pop();
+        }
+    }
+
+    fn find(&self, path: &[usize]) -> Option<&TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get(*first)?;
+        for index in rest {
+            node = node.children.get(*index)?;
+        }
+        Some(node)
+    }
+
+    fn node(&self, path: &[usize]) -> &TreeNode {
+        let mut node = &self.roots[path[0]];
+        for index in &path[1..] {
+            node = &node.children[*index];
+        }
+        node
+    }
+
+    /// Paths of the leaves at or below `path`, in tree order. The empty path is above the
+    /// roots.
+    fn leaves(&self, path: &[usize]) -> Vec<Vec<usize>> {
+        let mut leaves = Vec::new();
+        let nodes = match path.is_empty() {
+            true => &self.roots,
+            false => &self.node(path).children,
+        };
+        if nodes.is_empty() {
+            leaves.push(path.to_vec());
+        }
+        TreeMenu::walk(nodes, &mut path.to_vec(), &mut |path, node| {
+            if node.children.is_empty() {
+                leaves.push(path.to_vec());
+            }
+            true
+        });
+        leaves
+    }
+
+    fn check_state(&self, path: &[usize]) -> Check {
+        let leaves = self.leaves(path);
+        let checked = leaves
+            .iter()
+            .filter(|leaf| self.checked.contains(*leaf))
+            .count();
+        match checked {
+            0 => Check::None,
+            checked if checked == leaves.len() => Check::All,
+            _ => Check::Some,
+        }
+    }
+
+    /// Paths of the rows shown when the nodes in `expanded` are expanded, in tree order.
+    fn rows(&self, expanded: &HashSet<Vec<usize>>) -> Vec<Vec<usize>> {
+        let mut rows = Vec::new();
+        TreeMenu::walk(&self.roots, &mut Vec::new(), &mut |path, _| {
+            rows.push(path.to_vec());
+            expanded.contains(path)
+        });
+        rows
+    }
+
+    /// Lists the rows that are not hidden in collapsed nodes, keeping the highlight on the same
+    /// node, or on the nearest node above it that is still shown.
+    fn refresh(&mut self) {
+        let highlighted = self.visible.get(self.menu.selected_index).cloned();
+        let visible = self.rows(&self.expanded);
+        self.menu.options = visible
+            .iter()
+            .map(|path| self.node(path).label.clone())
+            .collect();
+        self.visible = visible;
+        if let Some(mut highlighted) = highlighted {
+            while !highlighted.is_empty() {
+                if let Some(index) = self.visible.iter().position(|path| *path == highlighted) {
+                    self.menu.selected_index = index;
+                    return;
+                }
+                highlighted.pop();
+            }
+        }
+        self.menu.selected_index = self
+            .menu
+            .selected_index
+            .min(self.visible.len().saturating_sub(1));
+    }
+
+    fn highlighted(&self) -> Option<Vec<usize>> {
+        self.visible.get(self.menu.selected_index).cloned()
+    }
+
+    fn highlight(&mut self, path: &[usize]) {
+        if let Some(index) = self.visible.iter().position(|visible| visible == path) {
+            self.menu.selected_index = index;
+        }
+    }
+}
+
+impl MenuLike for TreeMenu {
+    fn get_menu_mut(&mut self) -> &mut Menu {
+        &mut self.menu
+    }
+
+    fn get_menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    fn format_option(&self, index: usize) -> String {
+        let path = &self.visible[index];
+        let node = self.node(path);
+        let marker = match (node.children.is_empty(), self.expanded.contains(path)) {
+            (true, _) => " ",
+            (false, true) => "▾",
+            (false, false) => "▸",
+        };
+        let check = match self.check_state(path) {
+            Check::All => "[x]",
+            Check::Some => "[-]",
+            Check::None => "[ ]",
+        };
+        let indent = "  ".repeat(path.len() - 1);
+        format!("{}{} {} {}", indent, marker, check, node.label)
+    }
+
+    /// Lists the checked leaves by label path, e.g. `team/service`.
+    fn format_answer(&self, _selected: Option<&HashSet<usize>>) -> String {
+        let labels: Vec<String> = self
+            .get_checked_labels()
+            .iter()
+            .map(|labels| labels.join("/"))
+            .collect();
+        labels.join(", ")
+    }
+
+    fn is_multi_select(&self) -> bool {
+        true
+    }
+
+    /// Draws the whole tree again, since the inner menu only knows the bare labels of the rows.
+    fn on_up_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.menu.selected_index;
+        if let Some(previous) = (0..index).rev().find(|index| self.is_selectable(*index)) {
+            self.menu.selected_index = previous;
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_down_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let index = self.menu.selected_index;
+        let len = self.menu.options.len();
+        if let Some(next) = (index + 1..len).find(|index| self.is_selectable(*index)) {
+            self.menu.selected_index = next;
+            self.refresh_preview();
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    fn on_space_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        if self.menu.disabled.contains_key(&self.menu.selected_index) {
+            return Ok(());
+        }
+        let path = match self.highlighted() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let leaves = self.leaves(&path);
+        if self.check_state(&path) == Check::All {
+            for leaf in &leaves {
+                self.checked.remove(leaf);
+            }
+        } else {
+            self.checked.extend(leaves);
+        }
+        let checked = self.check_state(&path) == Check::All;
+        let label = self.node(&path).label.clone();
+        if let Some(hook) = &mut self.menu.on_toggle {
+            hook(self.menu.selected_index, &label, checked);
+        }
+        self.redraw()
+    }
+
+    fn on_expand_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let path = match self.highlighted() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if self.node(&path).children.is_empty() {
+            return Ok(());
+        }
+        if !self.expanded.insert(path.clone()) {
+            let mut child = path;
+            child.push(0);
+            self.highlight(&child);
+        }
+        self.refresh();
+        self.refresh_preview();
+        self.redraw()
+    }
+
+    fn on_collapse_key(&mut self) -> Result<(), Box<dyn StdError>> {
+        let mut path = match self.highlighted() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !self.expanded.remove(&path) {
+            path.pop();
+            if path.is_empty() {
+                return Ok(());
+            }
+            self.highlight(&path);
+        }
+        self.refresh();
+        self.refresh_preview();
+        self.redraw()
+    }
+
+    /// The rows of the checked leaves, counted as if the nodes above them were expanded so that
+    /// each of them has a row. The tree is left as it is shown.
+    fn on_break(&mut self) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
+        let mut expanded = self.expanded.clone();
+        for leaf in self.get_checked_paths() {
+            for depth in 1..leaf.len() {
+                expanded.insert(leaf[..depth].to_vec());
+            }
+        }
+        let selected: HashSet<usize> = self
+            .rows(&expanded)
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| self.checked.contains(*path))
+            .map(|(index, _)| index)
+            .collect();
+        Ok(Some(selected).filter(|selected| !selected.is_empty()))
+    }
+
+    /// Lists the whole tree, with the checked leaves as the default answer.
+    fn run_fallback(&mut self) -> Result<Outcome, Box<dyn StdError>> {
+        self.expand_all();
+        self.menu.selected_options = (0..self.visible.len())
+            .filter(|index| self.checked.contains(&self.visible[*index]))
+            .collect();
+        run_line_fallback(self)
+    }
+
+    /// Checks exactly the leaves at or below the answered rows.
+    fn on_answer(
+        &mut self,
+        answer: HashSet<usize>,
+    ) -> Result<Option<HashSet<usize>>, Box<dyn StdError>> {
+        self.checked = answer
+            .iter()
+            .flat_map(|index| self.leaves(&self.visible[*index]))
+            .collect();
+        self.on_break()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(label: &str) -> TreeNode {
+        TreeNode::leaf(String::from(label))
+    }
+
+    /// web (api, ui), db (pg (primary, replica)), cache
+    fn tree() -> TreeMenu {
+        let pg = TreeNode::new(String::from("pg"), vec![leaf("primary"), leaf("replica")]);
+        TreeMenu::new(
+            String::from("Services"),
+            vec![
+                TreeNode::new(String::from("web"), vec![leaf("api"), leaf("ui")]),
+                TreeNode::new(String::from("db"), vec![pg]),
+                leaf("cache"),
+            ],
+        )
+    }
+
+    #[test]
+    fn find_follows_paths_and_rejects_invalid_ones() {
+        let tree = tree();
+        assert_eq!(
+            tree.find(&[1, 0, 1]).map(|node| node.label.as_str()),
+            Some("replica")
+        );
+        assert_eq!(
+            tree.find(&[2]).map(|node| node.label.as_str()),
+            Some("cache")
+        );
+        assert!(tree.find(&[]).is_none());
+        assert!(tree.find(&[3]).is_none());
+        assert!(tree.find(&[2, 0]).is_none());
+    }
+
+    #[test]
+    fn leaves_lists_the_leaves_below_a_node_in_order() {
+        let tree = tree();
+        assert_eq!(tree.leaves(&[1]), vec![vec![1, 0, 0], vec![1, 0, 1]]);
+        assert_eq!(tree.leaves(&[2]), vec![vec![2]]);
+        assert_eq!(tree.leaves(&[]).len(), 5);
+    }
+
+    #[test]
+    fn check_state_counts_checked_leaves() {
+        let mut tree = tree();
+        tree.check(vec![1, 0, 0]);
+        assert_eq!(tree.check_state(&[1, 0, 0]), Check::All);
+        assert_eq!(tree.check_state(&[1]), Check::Some);
+        assert_eq!(tree.check_state(&[0]), Check::None);
+        tree.check(vec![1, 0, 1]);
+        assert_eq!(tree.check_state(&[1]), Check::All);
+    }
+
+    #[test]
+    fn check_ignores_invalid_paths() {
+        let mut tree = tree();
+        tree.check(vec![0, 5]);
+        tree.check(Vec::new());
+        tree.expand(vec![7]);
+        assert!(tree.get_checked_paths().is_empty());
+        assert_eq!(tree.get_options().len(), 3);
+    }
+
+    #[test]
+    fn checked_paths_and_labels_are_in_tree_order() {
+        let mut tree = tree();
+        tree.check(vec![2]);
+        tree.check(vec![0, 1]);
+        tree.check(vec![1]);
+        assert_eq!(
+            tree.get_checked_paths(),
+            vec![vec![0, 1], vec![1, 0, 0], vec![1, 0, 1], vec![2]]
+        );
+        let labels: Vec<String> = tree
+            .get_checked_labels()
+            .iter()
+            .map(|labels| labels.join("/"))
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["web/ui", "db/pg/primary", "db/pg/replica", "cache"]
+        );
+    }
+
+    #[test]
+    fn on_break_counts_rows_without_expanding() {
+        let mut tree = tree();
+        tree.check(vec![1, 0, 1]);
+        tree.check(vec![2]);
+        let selected = tree.on_break().unwrap();
+        // web, db, pg, primary, replica, cache
+        assert_eq!(selected, Some(HashSet::from([4, 5])));
+        assert_eq!(tree.get_options().len(), 3);
+    }
+
+    #[test]
+    fn on_answer_replaces_the_checked_leaves() {
+        let mut tree = tree();
+        tree.check(vec![2]);
+        tree.expand_all();
+        // web, api, ui, db, pg, primary, replica, cache
+        let selected = tree.on_answer(HashSet::from([0, 4])).unwrap();
+        assert_eq!(selected, Some(HashSet::from([1, 2, 5, 6])));
+        assert_eq!(tree.get_checked_paths().len(), 4);
+        assert_eq!(tree.on_answer(HashSet::new()).unwrap(), None);
+    }
+
+    #[test]
+    fn refresh_keeps_the_highlight_on_the_nearest_shown_node() {
+        let mut tree = tree();
+        tree.expand_all();
+        tree.menu.selected_index = 6;
+        tree.expanded.remove(&vec![1, 0]);
+        tree.refresh();
+        assert_eq!(tree.highlighted(), Some(vec![1, 0]));
+        tree.expanded.remove(&vec![1]);
+        tree.refresh();
+        assert_eq!(tree.highlighted(), Some(vec![1]));
+    }
+}
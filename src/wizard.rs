@@ -0,0 +1,100 @@
+use crate::{MenuLike, Outcome, Status};
+use crossterm::{
+    cursor,
+    terminal::{Clear, ClearType},
+};
+use std::collections::HashSet;
+use std::error::Error as StdError;
+
+/// The answer to one step of a `Wizard`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Answer {
+    /// The chosen options, `None` when nothing was chosen or the step has no options.
+    pub selected: Option<HashSet<usize>>,
+    /// The answer as shown in the summary, e.g. the text typed into an `Input`.
+    pub value: String,
+}
+
+/// Runs menus and prompts one after another. Each answered step is summarised on a line above
+/// the next one, and esc goes back to the previous step as it was left.
+pub struct Wizard {
+    steps: Vec<Box<dyn MenuLike>>,
+}
+
+impl Wizard {
+    pub fn new() -> Wizard {
+        Wizard { steps: Vec::new() }
+    }
+
+    pub fn step(&mut self, step: Box<dyn MenuLike>) {
+        self.steps.push(step);
+    }
+
+    pub fn get_steps(&self) -> &Vec<Box<dyn MenuLike>> {
+        &self.steps
+    }
+
+    /// Runs the steps and returns their answers in order, or `None` when the first step was
+    /// cancelled. Ctrl-c exits the process like `run` unless the step disabled that.
+    pub fn run(&mut self) -> Result<Option<Vec<Answer>>, Box<dyn StdError>> {
+        let mut answers: Vec<Answer> = Vec::new();
+        while answers.len() < self.steps.len() {
+            let step = &mut self.steps[answers.len()];
+            let interactive = step.is_interactive();
+            let outcome = step.run_outcome()?;
+            step.get_menu().exit_if_interrupted(&outcome);
+            match outcome.status {
+                Status::Interrupted => return Ok(None),
+                Status::Cancelled if answers.is_empty() || !interactive => return Ok(None),
+                Status::Cancelled => {
+                    step.get_menu_mut().erase_frame()?;
+                    answers.pop();
+                    self.erase_summary_line(answers.len())?;
+                }
+                Status::Submitted => {
+                    let answer = Wizard::answer(step.as_mut(), outcome, interactive)?;
+                    answers.push(answer);
+                }
+            }
+        }
+        Ok(Some(answers))
+    }
+
+    /// Turns a submitted step into its answer, replacing the step with its summary line.
+    fn answer(
+        step: &mut dyn MenuLike,
+        outcome: Outcome,
+        interactive: bool,
+    ) -> Result<Answer, Box<dyn StdError>> {
+        let value = step.format_answer(outcome.selected.as_ref());
+        if interactive {
+            let title = step.get_title().lines().collect::<Vec<&str>>().join(" ");
+            let mut_menu = step.get_menu_mut();
+            mut_menu.erase_frame()?;
+            let summary = format!("{} › {}", title, value);
+            write!(mut_menu.writer, "{}\r\n", summary.trim_start_matches(" › "))?;
+            mut_menu.writer.flush()?;
+        }
+        Ok(Answer {
+            selected: outcome.selected,
+            value,
+        })
+    }
+
+    /// Removes the summary line of the step at `index`, which is the last line printed.
+    fn erase_summary_line(&mut self, index: usize) -> Result<(), Box<dyn StdError>> {
+        let mut_menu = self.steps[index].get_menu_mut();
+        execute!(
+            mut_menu.writer,
+            cursor::MoveToPreviousLine(1),
+            Clear(ClearType::FromCursorDown)
+        )?;
+        Ok(())
+    }
+}
+
+impl Default for Wizard {
+    fn default() -> Self {
+        Wizard::new()
+    }
+}